@@ -0,0 +1,455 @@
+use crate::polyphonia::{
+    notable_notes, Amplitude, Envelope, Note, Oscillator, Waveform, SAMPLE_RATE,
+};
+use crate::timing::{MorseTiming, SampleTimeline};
+use crate::Letter;
+
+/// Settings of the audio rendering of morse code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioSettings {
+    pub timing: MorseTiming,
+    pub tone: Note,
+    pub volume: Amplitude,
+    pub envelope: Envelope,
+    pub waveform: Waveform,
+    pub sample_rate: u32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            timing: MorseTiming::default(),
+            tone: notable_notes::A4,
+            volume: Amplitude::Medium,
+            envelope: Envelope::default(),
+            waveform: Waveform::Sine,
+            sample_rate: SAMPLE_RATE,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Render all the samples of the letters, see [MorseAudioSource].
+    pub fn render<'a, T: IntoIterator<Item = Letter<'a>>>(&self, letters: T) -> Vec<f32> {
+        self.source(letters).collect()
+    }
+
+    /// Source generating the samples of the letters on demand.
+    pub fn source<'a, T: IntoIterator<Item = Letter<'a>>>(&self, letters: T) -> MorseAudioSource {
+        MorseAudioSource::new(self, letters)
+    }
+}
+
+/// Seconds of the envelope of waves that can't stop at a zero crossing without a click.
+const MIN_RISE: f32 = 0.001;
+
+/// Tone or silence of the audio, lasting `nsamples` samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment {
+    tone: bool,
+    nsamples: u32,
+    // seconds the envelope is spread over
+    duration: f32,
+}
+
+/// Samples of the audio where a letter is keyed, from the start of its first tone
+/// to the end of its last one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LetterSpan {
+    pub text: String,
+    /// Position of the letter among the ones rendered, spaces included.
+    pub index: usize,
+    /// Words are counted by the spaces before the letter.
+    pub word: usize,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Samples of morse code, between -1 and 1, generated on demand.
+///
+/// Letters are played by a single oscillator, that stops during silences:
+/// every tone starts from the phase where the previous one ended.
+/// Tones fade in and out under the envelope, without an envelope they are
+/// stretched to end at a zero crossing, taking the time from the following silences.
+/// The last tone is cut at the zero crossing before its end instead, so the audio
+/// lasts exactly as long as the keying. Waves that aren't 0 at their crossings would
+/// still click, they get the shortest envelope instead.
+///
+/// The keying is planned when the source is created, so its length is known up front.
+#[derive(Debug, Clone)]
+pub struct MorseAudioSource {
+    settings: AudioSettings,
+    oscillator: Oscillator,
+    segments: Vec<Segment>,
+    spans: Vec<LetterSpan>,
+    // position of the next sample
+    segment: usize,
+    offset: u32,
+    remaining: usize,
+}
+
+impl MorseAudioSource {
+    pub fn new<'a, T: IntoIterator<Item = Letter<'a>>>(
+        settings: &AudioSettings,
+        letters: T,
+    ) -> Self {
+        let mut settings = *settings;
+        if settings.envelope.rise <= 0.0 && !settings.waveform.is_zero_at_half_periods() {
+            settings.envelope.rise = MIN_RISE;
+        }
+        let oscillator = settings
+            .tone
+            .oscillator()
+            .with_waveform(settings.waveform)
+            .with_sample_rate(settings.sample_rate);
+        // without an envelope, follow the phase of the oscillator to find where tones
+        // cross zero
+        let track_phase = settings.envelope.rise <= 0.0;
+        let mut phase = oscillator.clone();
+        let mut segments: Vec<Segment> = Vec::new();
+        // samples the tones took from the timeline, given back by the following silences
+        let mut overrun = 0;
+
+        // lengths of the elements, before the tones take samples from the silences
+        let mut timeline = SampleTimeline::new(settings.sample_rate);
+        let mut spans: Vec<LetterSpan> = Vec::new();
+        let mut position: u64 = 0;
+        let mut word = 0;
+
+        let keying = settings.timing.keying_letters(letters);
+        // no silence follows the last tone to give its samples back
+        let last_tone = keying
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, (_, elements))| {
+                elements
+                    .iter()
+                    .rposition(|element| element.tone)
+                    .map(|element| (index, element))
+            });
+
+        for (index, (letter, elements)) in keying.into_iter().enumerate() {
+            let Letter(text, morse) = letter;
+            if morse == "/" {
+                word += 1;
+                continue;
+            }
+            let mut start = None;
+            let mut end = 0;
+            for (n, element) in elements.into_iter().enumerate() {
+                let nsamples = timeline.advance(element.duration) as u32;
+                if !element.tone {
+                    let given_back = overrun.min(nsamples);
+                    overrun -= given_back;
+                    segments.push(Segment {
+                        tone: false,
+                        nsamples: nsamples - given_back,
+                        duration: element.duration as f32,
+                    });
+                    position += (nsamples - given_back) as u64;
+                    continue;
+                }
+
+                let mut tone = nsamples;
+                let mut silence = 0;
+                if track_phase && last_tone == Some((index, n)) {
+                    // end at the zero crossing before the end of the timeline instead
+                    let given_back = overrun.min(nsamples);
+                    overrun -= given_back;
+                    tone = nsamples - given_back;
+                    phase.skip(tone);
+                    let since = phase.samples_since_zero_crossing();
+                    if since < tone {
+                        tone -= since;
+                        silence = since;
+                    }
+                } else if track_phase {
+                    phase.skip(nsamples);
+                    let extra = phase.samples_to_zero_crossing();
+                    phase.skip(extra);
+                    tone += extra;
+                    overrun += extra;
+                }
+                segments.push(Segment {
+                    tone: true,
+                    nsamples: tone,
+                    duration: element.duration as f32,
+                });
+                start = start.or(Some(position));
+                position += tone as u64;
+                end = position;
+                if silence > 0 {
+                    segments.push(Segment {
+                        tone: false,
+                        nsamples: silence,
+                        duration: 0.0,
+                    });
+                    position += silence as u64;
+                }
+            }
+            if let Some(start) = start {
+                spans.push(LetterSpan {
+                    text: text.to_owned(),
+                    index,
+                    word,
+                    start,
+                    end,
+                });
+            }
+        }
+
+        MorseAudioSource {
+            settings,
+            oscillator,
+            spans,
+            remaining: segments
+                .iter()
+                .map(|segment| segment.nsamples as usize)
+                .sum(),
+            segments,
+            segment: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.settings.sample_rate
+    }
+
+    /// Where every letter is keyed, spaces aside.
+    pub fn letter_spans(&self) -> &[LetterSpan] {
+        &self.spans
+    }
+
+    /// Where every word is keyed, from the start of its first letter to the end of the last.
+    pub fn word_spans(&self) -> Vec<LetterSpan> {
+        let mut words: Vec<LetterSpan> = Vec::new();
+        for span in self.spans.iter() {
+            match words.last_mut() {
+                Some(word) if word.word == span.word => {
+                    word.text.push_str(&span.text);
+                    word.end = span.end;
+                }
+                _ => words.push(span.clone()),
+            }
+        }
+        words
+    }
+
+    /// Total number of samples of the audio, including the ones already generated.
+    pub fn total_samples(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.nsamples as usize)
+            .sum()
+    }
+}
+
+impl Iterator for MorseAudioSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let segment = loop {
+            let segment = self.segments.get(self.segment)?;
+            if self.offset < segment.nsamples {
+                break *segment;
+            }
+            self.segment += 1;
+            self.offset = 0;
+        };
+        let t = self.offset;
+        self.offset += 1;
+        self.remaining -= 1;
+        if !segment.tone {
+            return Some(0.0);
+        }
+
+        let amplitude = self.settings.volume.scaling()
+            * self.settings.envelope.gain(
+                t as f32 / self.settings.sample_rate as f32,
+                segment.duration,
+            );
+        Some(amplitude * self.oscillator.next_sample())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for MorseAudioSource {}
+
+#[test]
+fn test_render_zero_crossings() {
+    use crate::morse_alphabet;
+
+    // a dot lasts 44.1 cycles, so every tone takes samples from the following gap
+    let settings = AudioSettings {
+        tone: Note(441.0),
+        envelope: Envelope {
+            rise: 0.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let letters = [morse_alphabet::E, morse_alphabet::E, morse_alphabet::E];
+    let samples = settings.render(letters);
+    let dot = f64::round(settings.timing.dot * settings.sample_rate as f64) as usize;
+    let gap = f64::round(settings.timing.letter_gap * settings.sample_rate as f64) as usize;
+    assert_eq!(samples.len(), 3 * dot + 2 * gap);
+
+    // every tone starts and ends close to zero, even without an envelope
+    let edge = settings.volume.scaling() * 0.1;
+    let mut tones: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in samples
+        .iter()
+        .enumerate()
+        .filter(|(_, sample)| **sample != 0.0)
+    {
+        match tones.last_mut() {
+            Some((_, end)) if i - *end < 100 => *end = i,
+            _ => tones.push((i, i)),
+        }
+    }
+    assert_eq!(tones.len(), 3);
+    // the first two tones run past their dot, the last one is cut before its end
+    assert!(tones[0].1 >= dot && tones[1].1 >= 2 * dot + gap);
+    assert!(tones[2].1 < samples.len() - 1);
+    for (start, end) in tones {
+        assert!(samples[start].abs() < edge && samples[end].abs() < edge);
+    }
+
+    // square waves are at full scale at their crossings, they get an envelope instead
+    let square = AudioSettings {
+        waveform: Waveform::Square,
+        ..settings
+    };
+    let samples = square.render(letters);
+    assert_eq!(samples.len(), 3 * dot + 2 * gap);
+    let keyed = samples.iter().position(|sample| *sample != 0.0).unwrap();
+    assert!(samples[keyed].abs() < edge);
+    assert!(samples[dot - 1].abs() < edge);
+}
+
+#[test]
+fn test_audio_source() {
+    use crate::morse_alphabet::{O, S, SPACE};
+
+    let letters = [S, O, S, SPACE, S, O, S];
+    for rise in [0.005, 0.0] {
+        let settings = AudioSettings {
+            envelope: Envelope {
+                rise,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut source = settings.source(letters);
+        let total = source.len();
+        assert_eq!(source.total_samples(), total);
+
+        // samples pulled in chunks, as an audio callback does
+        let mut samples: Vec<f32> = Vec::new();
+        while source.len() > 0 {
+            samples.extend(source.by_ref().take(1000));
+            assert_eq!(source.len(), total - samples.len());
+        }
+        assert_eq!(source.next(), None);
+        assert_eq!(samples.len(), total);
+        assert_eq!(samples, settings.render(letters));
+    }
+}
+
+#[test]
+fn test_letter_spans() {
+    use crate::morse_alphabet::{E, SPACE, T};
+
+    let settings = AudioSettings::default();
+    let source = settings.source([SPACE, E, T, SPACE, E]);
+    let samples = settings.render([SPACE, E, T, SPACE, E]);
+    let spans = source
+        .letter_spans()
+        .iter()
+        .map(|span| {
+            (
+                span.text.as_str(),
+                span.index,
+                span.word,
+                span.start,
+                span.end,
+            )
+        })
+        .collect::<Vec<_>>();
+    let rate = settings.sample_rate as f64;
+    let dot = f64::round(settings.timing.dot * rate) as u64;
+    let letter_gap = f64::round(settings.timing.letter_gap * rate) as u64;
+    let word_gap = f64::round(settings.timing.word_gap * rate) as u64;
+    let t_start = dot + letter_gap;
+    let e_start = t_start + 3 * dot + word_gap;
+    assert_eq!(
+        spans,
+        vec![
+            ("e", 1, 1, 0, dot),
+            ("t", 2, 1, t_start, t_start + 3 * dot),
+            ("e", 4, 2, e_start, e_start + dot),
+        ]
+    );
+    assert_eq!(e_start + dot, samples.len() as u64);
+    // silence right before and after every letter
+    for (_, _, _, start, end) in spans {
+        assert!(start == 0 || samples[start as usize - 1] == 0.0);
+        assert!(samples[start as usize..end as usize]
+            .iter()
+            .any(|s| *s != 0.0));
+    }
+
+    let words = source.word_spans();
+    assert_eq!(words.len(), 2);
+    assert_eq!(
+        (words[0].text.as_str(), words[0].end),
+        ("et", t_start + 3 * dot)
+    );
+}
+
+#[test]
+fn test_source_length() {
+    use crate::morse_alphabet::{A, I, P, R, S, SPACE};
+
+    // a minute of PARIS at 13 wpm, the units are fractions of samples
+    let settings = AudioSettings {
+        timing: MorseTiming::from_wpm(13.0),
+        sample_rate: 8000,
+        ..Default::default()
+    };
+    // without an envelope, at a tone whose elements aren't whole numbers of cycles
+    let no_envelope = AudioSettings {
+        tone: Note(441.0),
+        envelope: Envelope {
+            rise: 0.0,
+            ..Default::default()
+        },
+        ..settings
+    };
+    let letters = || std::iter::repeat_n([P, A, R, I, S, SPACE], 13).flatten();
+    let units = 50 * 13 - 7;
+    for settings in [settings, no_envelope] {
+        let source = settings.source(letters());
+        assert_eq!(source.len() as u64, (units * 8000 * 12 + 65) / 130);
+        assert!(source.letter_spans().last().unwrap().end <= source.len() as u64);
+    }
+    let source = settings.source(letters());
+    assert_eq!(
+        source.letter_spans().last().unwrap().end,
+        source.len() as u64
+    );
+
+    // a tone so low that a half cycle outlasts the gaps
+    let low = AudioSettings {
+        tone: Note(15.0),
+        timing: MorseTiming::from_wpm(40.0),
+        ..no_envelope
+    };
+    let source = low.source(letters());
+    assert_eq!(source.len() as u64, (units * 8000 * 3 + 50) / 100);
+}
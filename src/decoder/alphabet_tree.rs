@@ -0,0 +1,70 @@
+use crate::{morse_alphabet, Letter};
+
+#[derive(Debug, Default)]
+struct Node {
+    letter: Option<Letter<'static>>,
+    // children reached with a dot and with a dash
+    children: [Option<usize>; 2],
+}
+
+/// Binary tree of the morse alphabet: every dot goes to the left, every dash to the right.
+#[derive(Debug)]
+pub struct AlphabetTree {
+    nodes: Vec<Node>,
+}
+
+impl Default for AlphabetTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlphabetTree {
+    pub fn new() -> Self {
+        let mut tree = AlphabetTree {
+            nodes: vec![Node::default()],
+        };
+        for letter in morse_alphabet::ALPHABET {
+            let Letter(_, morse) = letter;
+            let mut node = 0;
+            for element in morse.bytes() {
+                let branch = Self::branch(element).unwrap();
+                node = match tree.nodes[node].children[branch] {
+                    Some(child) => child,
+                    None => {
+                        tree.nodes.push(Node::default());
+                        let child = tree.nodes.len() - 1;
+                        tree.nodes[node].children[branch] = Some(child);
+                        child
+                    }
+                };
+            }
+            tree.nodes[node].letter = Some(letter);
+        }
+        tree
+    }
+
+    fn branch(element: u8) -> Option<usize> {
+        match element {
+            b'.' => Some(0),
+            b'-' => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Letters whose code is a prefix of the elements, with the length of their code.
+    pub fn prefixes(&self, elements: &[u8]) -> Vec<(usize, Letter<'static>)> {
+        let mut found = Vec::new();
+        let mut node = 0;
+        for (i, &element) in elements.iter().enumerate() {
+            match Self::branch(element).and_then(|branch| self.nodes[node].children[branch]) {
+                Some(child) => node = child,
+                None => break,
+            }
+            if let Some(letter) = self.nodes[node].letter {
+                found.push((i + 1, letter));
+            }
+        }
+        found
+    }
+}
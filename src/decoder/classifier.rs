@@ -0,0 +1,283 @@
+use super::{DecodedLetter, KeyInterval};
+use crate::timing::MorseTiming;
+use crate::{morse_alphabet, Letter};
+use std::str::FromStr;
+
+/// Morse element recognised from the duration of a mark or a space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Element {
+    Dot,
+    Dash,
+    ElementGap,
+    LetterGap,
+    WordGap,
+}
+
+/// Classifies marks and spaces in morse elements given the duration of a dot (unit).
+///
+/// Marks shorter than 2 units are dots, longer ones dashes.
+/// Spaces shorter than 2 units separate elements, up to 5 units letters and longer ones words.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingClassifier {
+    unit_ms: f64,
+}
+
+impl TimingClassifier {
+    pub fn new(unit_ms: f64) -> Self {
+        TimingClassifier { unit_ms }
+    }
+
+    /// Estimate the unit from the marks, clustering them in dots and dashes.
+    pub fn from_intervals(intervals: &[KeyInterval]) -> Self {
+        let marks = intervals
+            .iter()
+            .filter(|interval| interval.mark && interval.duration_ms > 0.0)
+            .map(|interval| interval.duration_ms)
+            .collect::<Vec<f64>>();
+        if marks.is_empty() {
+            return Self::new(MorseTiming::default().dot * 1000.0);
+        }
+
+        let mut short = marks.iter().cloned().fold(f64::INFINITY, f64::min);
+        let mut long = marks.iter().cloned().fold(0_f64, f64::max);
+
+        if long < short * 2.0 {
+            // a single cluster: dots, unless the shortest space shows they're dashes
+            let mean = marks.iter().sum::<f64>() / marks.len() as f64;
+            let shortest_space = intervals
+                .iter()
+                .filter(|interval| !interval.mark && interval.duration_ms > 0.0)
+                .map(|interval| interval.duration_ms)
+                .fold(f64::INFINITY, f64::min);
+            return match shortest_space * 2.0 < mean {
+                true => Self::new(mean / 3.0),
+                false => Self::new(mean),
+            };
+        }
+
+        // two clusters: iterate k-means with k = 2 starting from the extremes
+        let mut threshold = (short + long) / 2.0;
+        for _ in 0..16 {
+            let (short_count, short_sum, long_count, long_sum) = marks.iter().fold(
+                (0, 0_f64, 0, 0_f64),
+                |(short_count, short_sum, long_count, long_sum), &mark| match mark <= threshold {
+                    true => (short_count + 1, short_sum + mark, long_count, long_sum),
+                    false => (short_count, short_sum, long_count + 1, long_sum + mark),
+                },
+            );
+            short = short_sum / short_count as f64;
+            long = long_sum / long_count as f64;
+            threshold = (short + long) / 2.0;
+        }
+
+        // dashes are 3 units long, so they count as three dots each
+        let (count, sum) = marks.iter().fold((0_f64, 0_f64), |(count, sum), &mark| {
+            match mark <= threshold {
+                true => (count + 1.0, sum + mark),
+                false => (count + 3.0, sum + mark),
+            }
+        });
+        Self::new(sum / count)
+    }
+
+    pub fn unit_ms(&self) -> f64 {
+        self.unit_ms
+    }
+
+    pub fn classify(&self, interval: &KeyInterval) -> Element {
+        let units = interval.duration_ms / self.unit_ms;
+        match interval.mark {
+            true if units < 2.0 => Element::Dot,
+            true => Element::Dash,
+            false if units < 2.0 => Element::ElementGap,
+            false if units < 5.0 => Element::LetterGap,
+            false => Element::WordGap,
+        }
+    }
+
+    /// Confidence of the classification, 1 at the nominal length of the element
+    /// and decreasing to 0 at the threshold with the other element on the same side.
+    /// Past the nominal length on a side without other elements it stays 1.
+    pub fn confidence(&self, interval: &KeyInterval) -> f32 {
+        let units = interval.duration_ms / self.unit_ms;
+        let (nominal, threshold) = match self.classify(interval) {
+            Element::Dot | Element::ElementGap if units <= 1.0 => return 1.0,
+            Element::Dot | Element::ElementGap => (1.0, 2.0),
+            Element::Dash if units >= 3.0 => return 1.0,
+            Element::Dash => (3.0, 2.0),
+            Element::LetterGap if units < 3.0 => (3.0, 2.0),
+            Element::LetterGap => (3.0, 5.0),
+            Element::WordGap if units >= 7.0 => return 1.0,
+            Element::WordGap => (7.0, 5.0),
+        };
+        let distance = f64::abs(f64::ln(units / nominal)) / f64::abs(f64::ln(threshold / nominal));
+        (1.0 - distance).clamp(0.0, 1.0) as f32
+    }
+
+    pub fn decode(&self, intervals: &[KeyInterval]) -> Result<Vec<Letter<'static>>, String> {
+        Ok(self
+            .decode_timed(intervals)?
+            .into_iter()
+            .map(|decoded| decoded.letter)
+            .collect())
+    }
+
+    /// Decode the intervals keeping the position in time of every letter and the
+    /// confidence of its weakest element (including the gap that closes it).
+    pub fn decode_timed(&self, intervals: &[KeyInterval]) -> Result<Vec<DecodedLetter>, String> {
+        let mut letters: Vec<DecodedLetter> = Vec::new();
+        let mut code = String::new();
+        let mut current = DecodedLetter {
+            letter: morse_alphabet::SPACE,
+            start_ms: 0.0,
+            end_ms: 0.0,
+            confidence: 1.0,
+        };
+
+        fn push_letter(
+            letters: &mut Vec<DecodedLetter>,
+            code: &mut String,
+            current: &DecodedLetter,
+        ) -> Result<(), String> {
+            if !code.is_empty() {
+                let letter = Letter::from_str(code)
+                    .map_err(|err| format!("{} at {:.0} ms", err, current.start_ms))?;
+                letters.push(DecodedLetter { letter, ..*current });
+                code.clear();
+            }
+            Ok(())
+        }
+
+        for interval in intervals {
+            let confidence = self.confidence(interval);
+            match self.classify(interval) {
+                Element::Dot | Element::Dash => {
+                    if code.is_empty() {
+                        current.start_ms = interval.start_ms;
+                        current.confidence = 1.0;
+                    }
+                    code.push(self.symbol(interval));
+                    current.end_ms = interval.end_ms();
+                    current.confidence = current.confidence.min(confidence);
+                }
+                Element::ElementGap => current.confidence = current.confidence.min(confidence),
+                Element::LetterGap => {
+                    current.confidence = current.confidence.min(confidence);
+                    push_letter(&mut letters, &mut code, &current)?;
+                }
+                Element::WordGap => {
+                    current.confidence = current.confidence.min(confidence);
+                    push_letter(&mut letters, &mut code, &current)?;
+                    if letters
+                        .last()
+                        .is_some_and(|last| last.letter != morse_alphabet::SPACE)
+                    {
+                        letters.push(DecodedLetter {
+                            letter: morse_alphabet::SPACE,
+                            start_ms: interval.start_ms,
+                            end_ms: interval.end_ms(),
+                            confidence,
+                        });
+                    }
+                }
+            }
+        }
+        push_letter(&mut letters, &mut code, &current)?;
+
+        if letters
+            .last()
+            .is_some_and(|last| last.letter == morse_alphabet::SPACE)
+        {
+            letters.pop();
+        }
+        Ok(letters)
+    }
+
+    fn symbol(&self, interval: &KeyInterval) -> char {
+        match self.classify(interval) {
+            Element::Dot => '.',
+            _ => '-',
+        }
+    }
+}
+
+#[test]
+fn test_decode_key_log() {
+    use super::parse_key_log;
+
+    // "sos e" at roughly 60 ms per dot, with some jitter on every element
+    let log = [
+        "55 70 62 58 66", // s
+        "190",
+        "175 60 190 58 180", // o
+        "170",
+        "70 55 59 64 60", // s
+        "430",
+        "65", // e
+    ];
+    let intervals = parse_key_log(&log).unwrap();
+    let classifier = TimingClassifier::from_intervals(&intervals);
+    assert!((classifier.unit_ms() - 60.0).abs() < 5.0);
+    assert_eq!(
+        classifier.decode(&intervals).unwrap(),
+        vec![
+            morse_alphabet::S,
+            morse_alphabet::O,
+            morse_alphabet::S,
+            morse_alphabet::SPACE,
+            morse_alphabet::E,
+        ]
+    );
+
+    // a lone dash is only recognised as such thanks to the spaces
+    let intervals = parse_key_log(&["0,300", "400,700"]).unwrap();
+    assert_eq!(
+        TimingClassifier::from_intervals(&intervals)
+            .decode(&intervals)
+            .unwrap(),
+        vec![morse_alphabet::M]
+    );
+}
+
+#[test]
+fn test_decode_timed() {
+    use super::parse_key_log;
+
+    // "em" at 100 ms per dot, the first dash is sloppy and close to a dot
+    let intervals = parse_key_log(&["0,100", "400,610", "700,1000"]).unwrap();
+    let classifier = TimingClassifier::new(100.0);
+    let decoded = classifier.decode_timed(&intervals).unwrap();
+
+    assert_eq!(
+        decoded.iter().map(|x| &x.letter).collect::<Vec<_>>(),
+        vec![&morse_alphabet::E, &morse_alphabet::M]
+    );
+    assert_eq!((decoded[0].start_ms, decoded[0].end_ms), (0.0, 100.0));
+    assert_eq!((decoded[1].start_ms, decoded[1].end_ms), (400.0, 1000.0));
+    assert!(decoded[0].confidence > 0.9);
+    assert!(decoded[1].confidence < 0.2);
+}
+
+#[test]
+fn test_confidence() {
+    let classifier = TimingClassifier::new(100.0);
+    let confidence = |mark: bool, duration_ms: f64| {
+        classifier.confidence(&KeyInterval {
+            mark,
+            start_ms: 0.0,
+            duration_ms,
+        })
+    };
+    // a slow dash and a quick dot are far from any other element
+    assert_eq!(confidence(true, 500.0), 1.0);
+    assert_eq!(confidence(true, 40.0), 1.0);
+    assert_eq!(confidence(false, 40.0), 1.0);
+    assert_eq!(confidence(false, 900.0), 1.0);
+    // close to the threshold between dots and dashes
+    assert!(confidence(true, 190.0) < 0.1);
+    assert!(confidence(true, 210.0) < 0.2);
+    // a letter gap is ambiguous on both sides
+    assert_eq!(confidence(false, 300.0), 1.0);
+    assert!(confidence(false, 480.0) < 0.1);
+    assert!(confidence(false, 210.0) < 0.2);
+}
@@ -0,0 +1,245 @@
+use super::Dictionary;
+use crate::{morse_alphabet, Letter};
+use std::fmt;
+use std::str::FromStr;
+
+/// Penalty, in log probability, of every element changed by a correction.
+const EDIT_PENALTY: f64 = 6.0;
+const BEAM_WIDTH: usize = 64;
+/// Nearest letters written for every unknown code.
+const SUGGESTIONS: usize = 5;
+
+/// Edit distance between two codes, counting inserted, removed and swapped elements.
+pub fn element_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, element_a) in a.bytes().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, element_b) in b.iter().enumerate() {
+            let swap = previous[j] + usize::from(element_a != *element_b);
+            current.push(swap.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Letters of the alphabet sorted by the element edit distance of their code from `code`.
+pub fn nearest_codes(code: &str) -> Vec<(Letter<'static>, usize)> {
+    let mut nearest = morse_alphabet::ALPHABET
+        .iter()
+        .map(|letter| {
+            let Letter(_, morse) = letter;
+            (*letter, element_distance(code, morse))
+        })
+        .collect::<Vec<(Letter<'static>, usize)>>();
+    nearest.sort_by_key(|(_, distance)| *distance);
+    nearest
+}
+
+/// Reading of a line as written and after the correction of its codes.
+///
+/// Codes missing from the alphabet appear in the raw reading between square brackets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correction {
+    pub raw: String,
+    pub corrected: String,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Letters considered in place of a code missing from the alphabet, nearest first,
+/// with their edit distance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub code: String,
+    pub letters: Vec<(Letter<'static>, usize)>,
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} →", self.code)?;
+        for (Letter(text, _), distance) in self.letters.iter().take(SUGGESTIONS) {
+            write!(f, " {}({})", text, distance)?;
+        }
+        if self.letters.len() > SUGGESTIONS {
+            write!(f, " …")?;
+        }
+        write!(f, "]")
+    }
+}
+
+struct Hypothesis {
+    text: String,
+    score: f64,
+}
+
+/// Fixes mistyped codes picking, among the closest valid codes, the letters that
+/// make the most likely word according to a [Dictionary].
+pub struct Corrector<'a> {
+    dictionary: &'a Dictionary,
+}
+
+impl<'a> Corrector<'a> {
+    pub fn new(dictionary: &'a Dictionary) -> Self {
+        Corrector { dictionary }
+    }
+
+    /// Correct a line of morse with letters separated by whitespace and words by `/`.
+    pub fn correct_line(&self, line: &str) -> Correction {
+        let mut raw_words: Vec<String> = Vec::new();
+        let mut corrected_words: Vec<String> = Vec::new();
+        let mut suggestions: Vec<Suggestion> = Vec::new();
+        for word in line.split('/') {
+            let codes = word.split_whitespace().collect::<Vec<&str>>();
+            if codes.is_empty() {
+                continue;
+            }
+            let (raw, corrected) = self.correct_word(&codes);
+            suggestions.extend(codes.iter().filter_map(|code| Self::suggestion(code)));
+            raw_words.push(raw);
+            corrected_words.push(corrected);
+        }
+        Correction {
+            raw: raw_words.join(" "),
+            corrected: corrected_words.join(" "),
+            suggestions,
+        }
+    }
+
+    /// Letters considered for a morse code missing from the alphabet.
+    fn suggestion(code: &str) -> Option<Suggestion> {
+        if Letter::from_str(code).is_ok() {
+            return None;
+        }
+        let letters = Self::candidates(code);
+        (!letters.is_empty()).then(|| Suggestion {
+            code: code.to_owned(),
+            letters,
+        })
+    }
+
+    fn candidates(code: &str) -> Vec<(Letter<'static>, usize)> {
+        let is_morse = code
+            .bytes()
+            .all(|element| element == b'.' || element == b'-');
+        match Letter::from_str(code) {
+            // known letters may still be wrong, but only by a single element
+            Ok(letter) if is_morse => std::iter::once((letter, 0))
+                .chain(
+                    nearest_codes(code)
+                        .into_iter()
+                        .filter(|(_, distance)| *distance == 1),
+                )
+                .collect(),
+            Ok(letter) => vec![(letter, 0)],
+            Err(_) if is_morse => {
+                let nearest = nearest_codes(code);
+                let closest = nearest[0].1;
+                nearest
+                    .into_iter()
+                    .filter(|(_, distance)| *distance <= closest + 1)
+                    .collect()
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn correct_word(&self, codes: &[&str]) -> (String, String) {
+        let raw = codes
+            .iter()
+            .map(|code| match Letter::from_str(code) {
+                Ok(Letter(text, _)) => text.to_owned(),
+                Err(_) => format!("[{}]", code),
+            })
+            .collect::<String>();
+
+        let mut beam = vec![Hypothesis {
+            text: String::new(),
+            score: 0.0,
+        }];
+        for code in codes {
+            let candidates = Self::candidates(code);
+            if candidates.is_empty() {
+                // nothing resembles the code, keep it as it is
+                beam.iter_mut()
+                    .for_each(|hypothesis| hypothesis.text.push_str(&format!("[{}]", code)));
+                continue;
+            }
+
+            let mut next = Vec::new();
+            for hypothesis in beam.iter() {
+                let previous = hypothesis.text.chars().last();
+                for (letter, distance) in candidates.iter() {
+                    let Letter(text, _) = letter;
+                    let ch = text.chars().next().unwrap();
+                    next.push(Hypothesis {
+                        text: format!("{}{}", hypothesis.text, text),
+                        score: hypothesis.score + self.dictionary.char_log_prob(previous, ch)
+                            - EDIT_PENALTY * *distance as f64,
+                    });
+                }
+            }
+            next.sort_by(|a, b| {
+                let score_a = a.score + self.dictionary.prefix_bonus(&a.text);
+                let score_b = b.score + self.dictionary.prefix_bonus(&b.text);
+                score_b.total_cmp(&score_a)
+            });
+            next.truncate(BEAM_WIDTH);
+            beam = next;
+        }
+
+        let corrected = beam
+            .into_iter()
+            .map(|hypothesis| {
+                let score = hypothesis.score
+                    + self.dictionary.end_log_prob(hypothesis.text.chars().last())
+                    + self.dictionary.word_bonus(&hypothesis.text);
+                (hypothesis.text, score)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(text, _)| text)
+            .unwrap_or_default();
+        (raw, corrected)
+    }
+}
+
+#[test]
+fn test_element_distance() {
+    assert_eq!(element_distance(".-.-", ".-.."), 1);
+    assert_eq!(element_distance(".-.-", ".-."), 1);
+    assert_eq!(element_distance("", "-.-"), 3);
+
+    let nearest = nearest_codes(".-.-");
+    assert_eq!(nearest[0].1, 1);
+    assert!(nearest
+        .iter()
+        .take_while(|(_, distance)| *distance == 1)
+        .any(|(letter, _)| *letter == morse_alphabet::L));
+}
+
+#[test]
+fn test_correct_line() {
+    let dictionary = Dictionary::default();
+    let corrector = Corrector::new(&dictionary);
+
+    let correction = corrector.correct_line(".... . .-.- .-.. --- / .-- --- .-. .-.. -..");
+    assert_eq!(correction.raw, "he[.-.-]lo world");
+    assert_eq!(correction.corrected, "hello world");
+    assert_eq!(correction.suggestions.len(), 1);
+    let suggestion = &correction.suggestions[0];
+    assert_eq!(suggestion.letters[0].1, 1);
+    assert!(suggestion
+        .letters
+        .iter()
+        .any(|(letter, distance)| *letter == morse_alphabet::L && *distance == 1));
+    assert_eq!(
+        suggestion.to_string(),
+        "[.-.- → j(1) k(1) l(1) q(1) r(1) …]"
+    );
+
+    // a known letter is changed only when it makes a dictionary word
+    let correction = corrector.correct_line("- .... .. / -.- ..-. --.");
+    assert_eq!(correction.raw, "thi kfg");
+    assert_eq!(correction.corrected, "the kfg");
+    assert!(correction.suggestions.is_empty());
+}
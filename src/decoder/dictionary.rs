@@ -0,0 +1,121 @@
+use crate::{morse_alphabet, Letter};
+use std::collections::{HashMap, HashSet};
+
+const DEFAULT_WORDS: &str = include_str!("words.txt");
+/// Bonus, in log probability, of a reading that is a word of the dictionary.
+const WORD_BONUS: f64 = 8.0;
+/// Bonus given while searching to partial readings that may still become a dictionary word.
+const PREFIX_BONUS: f64 = 4.0;
+const WORD_START: char = '^';
+const WORD_END: char = '$';
+
+/// Word list with a character bigram model trained on it, used to rank readings of
+/// ambiguous morse.
+#[derive(Debug)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    prefixes: HashSet<String>,
+    bigrams: HashMap<(char, char), u32>,
+    unigrams: HashMap<char, u32>,
+}
+
+impl Default for Dictionary {
+    /// Dictionary of common english words and ham radio abbreviations.
+    fn default() -> Self {
+        Self::from_words(DEFAULT_WORDS.lines())
+    }
+}
+
+impl Dictionary {
+    /// Build the dictionary from a word list, words with characters outside of the
+    /// morse alphabet are ignored.
+    pub fn from_words<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut dictionary = Dictionary {
+            words: HashSet::new(),
+            prefixes: HashSet::new(),
+            bigrams: HashMap::new(),
+            unigrams: HashMap::new(),
+        };
+        for word in words {
+            let word = word.as_ref().trim().to_ascii_lowercase();
+            if word.is_empty() || !word.chars().all(Self::in_alphabet) {
+                continue;
+            }
+            let chars = std::iter::once(WORD_START)
+                .chain(word.chars())
+                .chain(std::iter::once(WORD_END))
+                .collect::<Vec<char>>();
+            for pair in chars.windows(2) {
+                *dictionary.bigrams.entry((pair[0], pair[1])).or_insert(0) += 1;
+                *dictionary.unigrams.entry(pair[0]).or_insert(0) += 1;
+            }
+            for end in 1..word.len() {
+                dictionary.prefixes.insert(word[..end].to_owned());
+            }
+            dictionary.words.insert(word);
+        }
+        dictionary
+    }
+
+    fn in_alphabet(ch: char) -> bool {
+        morse_alphabet::ALPHABET.iter().any(|letter| {
+            let Letter(text, _) = letter;
+            text.starts_with(ch)
+        })
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    pub fn is_prefix(&self, prefix: &str) -> bool {
+        self.prefixes.contains(prefix)
+    }
+
+    /// Bonus to add to the score of a complete reading of a word.
+    pub fn word_bonus(&self, word: &str) -> f64 {
+        match self.contains(word) {
+            true => WORD_BONUS,
+            false => 0.0,
+        }
+    }
+
+    /// Bonus to add to the score of a partial reading while searching, so that
+    /// the beginning of dictionary words is not pruned.
+    pub fn prefix_bonus(&self, prefix: &str) -> f64 {
+        match self.is_prefix(prefix) || self.contains(prefix) {
+            true => PREFIX_BONUS,
+            false => 0.0,
+        }
+    }
+
+    /// Log probability of `ch` following `previous` (`None` at the start of a word),
+    /// with add-one smoothing over the alphabet and the end of word.
+    pub fn char_log_prob(&self, previous: Option<char>, ch: char) -> f64 {
+        let previous = previous.unwrap_or(WORD_START);
+        let pair_count = self.bigrams.get(&(previous, ch)).cloned().unwrap_or(0);
+        let previous_count = self.unigrams.get(&previous).cloned().unwrap_or(0);
+        let vocabulary = morse_alphabet::ALPHABET.len() + 1;
+        f64::ln((pair_count + 1) as f64 / (previous_count as usize + vocabulary) as f64)
+    }
+
+    /// Log probability of a word ending after `last`.
+    pub fn end_log_prob(&self, last: Option<char>) -> f64 {
+        self.char_log_prob(last, WORD_END)
+    }
+
+    /// Log probability of a whole word under the character model.
+    pub fn word_log_prob(&self, word: &str) -> f64 {
+        let mut previous = None;
+        let mut log_prob = 0_f64;
+        for ch in word.chars() {
+            log_prob += self.char_log_prob(previous, ch);
+            previous = Some(ch);
+        }
+        log_prob + self.end_log_prob(previous)
+    }
+}
@@ -0,0 +1,131 @@
+use super::KeyInterval;
+
+/// Parse a key log into alternating mark and space intervals.
+///
+/// Two formats are accepted:
+/// - `down_ms,up_ms`: one key press per line, as timestamps of the key going down and up
+/// - `duration_ms`: alternating mark and space durations (starting with a mark),
+///   separated by whitespace or newlines
+///
+/// Empty lines and lines starting with `#` are ignored, the two formats can't be mixed.
+pub fn parse_key_log<S: AsRef<str>>(lines: &[S]) -> Result<Vec<KeyInterval>, String> {
+    let entries = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.as_ref().trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<(usize, &str)>>();
+
+    match entries.first() {
+        None => Ok(Vec::new()),
+        Some((_, line)) if line.contains(',') => parse_transitions(&entries),
+        Some(_) => parse_durations(&entries),
+    }
+}
+
+fn parse_ms(value: &str, line_number: usize) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(ms) if ms.is_finite() && ms >= 0.0 => Ok(ms),
+        _ => Err(format!(
+            "Invalid time value {:?} at line {}",
+            value, line_number
+        )),
+    }
+}
+
+fn parse_transitions(entries: &[(usize, &str)]) -> Result<Vec<KeyInterval>, String> {
+    let mut intervals: Vec<KeyInterval> = Vec::new();
+    let mut last_up: Option<f64> = None;
+
+    for &(line_number, line) in entries {
+        let (down, up) = match line.split_once(',') {
+            Some((down, up)) => (parse_ms(down, line_number)?, parse_ms(up, line_number)?),
+            None => return Err(format!("Expected down_ms,up_ms at line {}", line_number)),
+        };
+        if up < down {
+            return Err(format!(
+                "Key released before being pressed at line {}",
+                line_number
+            ));
+        }
+        if let Some(last_up) = last_up {
+            if down < last_up {
+                return Err(format!(
+                    "Key pressed before the previous release at line {}",
+                    line_number
+                ));
+            }
+            intervals.push(KeyInterval {
+                mark: false,
+                start_ms: last_up,
+                duration_ms: down - last_up,
+            });
+        }
+        intervals.push(KeyInterval {
+            mark: true,
+            start_ms: down,
+            duration_ms: up - down,
+        });
+        last_up = Some(up);
+    }
+    Ok(intervals)
+}
+
+fn parse_durations(entries: &[(usize, &str)]) -> Result<Vec<KeyInterval>, String> {
+    let mut intervals: Vec<KeyInterval> = Vec::new();
+    let mut time_ms = 0_f64;
+    let mut mark = true;
+
+    for &(line_number, line) in entries {
+        if line.contains(',') {
+            return Err(format!(
+                "Mixed down_ms,up_ms and duration formats at line {}",
+                line_number
+            ));
+        }
+        for value in line.split_whitespace() {
+            let duration_ms = parse_ms(value, line_number)?;
+            intervals.push(KeyInterval {
+                mark,
+                start_ms: time_ms,
+                duration_ms,
+            });
+            time_ms += duration_ms;
+            mark = !mark;
+        }
+    }
+    Ok(intervals)
+}
+
+#[test]
+fn test_parse_key_log() {
+    let transitions = parse_key_log(&["# key down, key up", "1000,1100", "1200,1500"]).unwrap();
+    let durations = parse_key_log(&["100 100", "300"]).unwrap();
+    let expected = vec![
+        KeyInterval {
+            mark: true,
+            start_ms: 1000.0,
+            duration_ms: 100.0,
+        },
+        KeyInterval {
+            mark: false,
+            start_ms: 1100.0,
+            duration_ms: 100.0,
+        },
+        KeyInterval {
+            mark: true,
+            start_ms: 1200.0,
+            duration_ms: 300.0,
+        },
+    ];
+    assert_eq!(transitions, expected);
+    assert_eq!(
+        durations
+            .iter()
+            .map(|i| i.duration_ms)
+            .collect::<Vec<f64>>(),
+        vec![100.0, 100.0, 300.0]
+    );
+    assert!(parse_key_log(&["1200,1100"]).is_err());
+    assert!(parse_key_log(&["100", "100,200"]).is_err());
+}
@@ -0,0 +1,58 @@
+//public modules
+pub mod alphabet_tree;
+pub mod classifier;
+pub mod correct;
+pub mod dictionary;
+pub mod key_log;
+pub mod segment;
+
+// public uses
+pub use alphabet_tree::AlphabetTree;
+pub use classifier::{Element, TimingClassifier};
+pub use correct::{Correction, Corrector, Suggestion};
+pub use dictionary::Dictionary;
+pub use key_log::parse_key_log;
+pub use segment::{Reading, Segmenter};
+
+use crate::Letter;
+
+/// Interval of time where the key is either pressed (mark) or released (space).
+///
+/// This is the common input of the decoder: key logs are turned into intervals
+/// and then classified by a [TimingClassifier], regardless of where the timings came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyInterval {
+    pub mark: bool,
+    pub start_ms: f64,
+    pub duration_ms: f64,
+}
+
+impl KeyInterval {
+    pub fn end_ms(&self) -> f64 {
+        self.start_ms + self.duration_ms
+    }
+}
+
+/// Letter recognised by the decoder, with its position in the recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodedLetter {
+    pub letter: Letter<'static>,
+    pub start_ms: f64,
+    pub end_ms: f64,
+    pub confidence: f32,
+}
+
+impl DecodedLetter {
+    /// Serialize the letter as a single line JSON object.
+    pub fn json_line(&self) -> String {
+        let Letter(text, morse) = self.letter;
+        format!(
+            "{{\"char\":\"{}\",\"morse\":\"{}\",\"start_ms\":{:.1},\"end_ms\":{:.1},\"confidence\":{:.3}}}",
+            text.escape_default(),
+            morse,
+            self.start_ms,
+            self.end_ms,
+            self.confidence
+        )
+    }
+}
@@ -0,0 +1,129 @@
+use super::{AlphabetTree, Dictionary};
+use crate::Letter;
+
+const MIN_BEAM_WIDTH: usize = 64;
+
+/// Possible reading of a morse sequence, scored by its log probability.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    pub text: String,
+    pub score: f64,
+}
+
+impl Reading {
+    fn empty() -> Self {
+        Reading {
+            text: String::new(),
+            score: 0.0,
+        }
+    }
+}
+
+/// Splits morse without letter separators in the most likely letters.
+///
+/// Every way of walking the elements through the [AlphabetTree] is a reading,
+/// readings are ranked with the character model and the words of a [Dictionary].
+pub struct Segmenter<'a> {
+    tree: AlphabetTree,
+    dictionary: &'a Dictionary,
+}
+
+impl<'a> Segmenter<'a> {
+    pub fn new(dictionary: &'a Dictionary) -> Self {
+        Segmenter {
+            tree: AlphabetTree::new(),
+            dictionary,
+        }
+    }
+
+    /// Best readings of a single word, made only of dots and dashes.
+    pub fn segment_word(&self, elements: &str, candidates: usize) -> Result<Vec<Reading>, String> {
+        if let Some(illegal) = elements.chars().find(|ch| *ch != '.' && *ch != '-') {
+            return Err(format!(
+                "Illegal character {:?} in morse word: {}",
+                illegal, elements
+            ));
+        }
+
+        // readings ending at each position of the elements
+        let elements = elements.as_bytes();
+        let beam_width = usize::max(MIN_BEAM_WIDTH, candidates * 8);
+        let mut beams: Vec<Vec<Reading>> = vec![Vec::new(); elements.len() + 1];
+        beams[0].push(Reading::empty());
+
+        for position in 0..elements.len() {
+            let mut beam = std::mem::take(&mut beams[position]);
+            beam.sort_by(|a, b| {
+                let score_a = a.score + self.dictionary.prefix_bonus(&a.text);
+                let score_b = b.score + self.dictionary.prefix_bonus(&b.text);
+                score_b.total_cmp(&score_a)
+            });
+            beam.truncate(beam_width);
+
+            for reading in beam {
+                let previous = reading.text.chars().last();
+                for (length, letter) in self.tree.prefixes(&elements[position..]) {
+                    let Letter(text, _) = letter;
+                    let ch = text.chars().next().unwrap();
+                    beams[position + length].push(Reading {
+                        text: format!("{}{}", reading.text, text),
+                        score: reading.score + self.dictionary.char_log_prob(previous, ch),
+                    });
+                }
+            }
+        }
+
+        let mut readings = beams.pop().unwrap();
+        for reading in readings.iter_mut() {
+            reading.score += self.dictionary.end_log_prob(reading.text.chars().last())
+                + self.dictionary.word_bonus(&reading.text);
+        }
+        readings.sort_by(|a, b| b.score.total_cmp(&a.score));
+        readings.truncate(candidates);
+        Ok(readings)
+    }
+
+    /// Best readings of a line, where words are separated by whitespace or `/`.
+    pub fn segment_line(&self, line: &str, candidates: usize) -> Result<Vec<Reading>, String> {
+        let mut readings = vec![Reading::empty()];
+        for word in line
+            .split(|ch: char| ch.is_whitespace() || ch == '/')
+            .filter(|word| !word.is_empty())
+        {
+            let word_readings = self.segment_word(word, candidates)?;
+            let mut combined = Vec::new();
+            for reading in readings.iter() {
+                for word_reading in word_readings.iter() {
+                    combined.push(Reading {
+                        text: match reading.text.is_empty() {
+                            true => word_reading.text.clone(),
+                            false => format!("{} {}", reading.text, word_reading.text),
+                        },
+                        score: reading.score + word_reading.score,
+                    });
+                }
+            }
+            combined.sort_by(|a, b| b.score.total_cmp(&a.score));
+            combined.truncate(candidates);
+            readings = combined;
+        }
+        Ok(readings)
+    }
+}
+
+#[test]
+fn test_segment() {
+    let dictionary = Dictionary::default();
+    let segmenter = Segmenter::new(&dictionary);
+
+    let readings = segmenter.segment_word("......-...-..---", 5).unwrap();
+    assert_eq!(readings.len(), 5);
+    assert_eq!(readings[0].text, "hello");
+    assert!(readings
+        .windows(2)
+        .all(|pair| pair[0].score >= pair[1].score));
+
+    let readings = segmenter.segment_line("...---... / -.-.--.-", 3).unwrap();
+    assert_eq!(readings[0].text, "sos cq");
+    assert!(segmenter.segment_word("..x-", 3).is_err());
+}
@@ -0,0 +1,101 @@
+/// Writer of values of any number of bits, most significant bit first.
+#[derive(Debug, Default)]
+pub(crate) struct BitWriter {
+    bytes: Vec<u8>,
+    // bits not yet making a whole byte, in the low `pending` bits
+    accumulator: u64,
+    pending: u32,
+}
+
+impl BitWriter {
+    pub(crate) fn new() -> Self {
+        BitWriter::default()
+    }
+
+    /// Append the low `bits` bits of `value`, up to 32.
+    pub(crate) fn write(&mut self, value: u64, bits: u32) {
+        debug_assert!(bits <= 32);
+        if bits == 0 {
+            return;
+        }
+        self.accumulator = self.accumulator << bits | value & ((1 << bits) - 1);
+        self.pending += bits;
+        while self.pending >= 8 {
+            self.pending -= 8;
+            self.bytes.push((self.accumulator >> self.pending) as u8);
+        }
+    }
+
+    /// Append a two's complement value of `bits` bits.
+    pub(crate) fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// Append `zeros` zero bits followed by a one.
+    pub(crate) fn write_unary(&mut self, mut zeros: u32) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros + 1);
+    }
+
+    /// Append a value with Rice coding of parameter `k`: the sign is folded in the
+    /// lowest bit, then the high bits are written in unary and the `k` low bits as they are.
+    pub(crate) fn write_rice(&mut self, value: i32, k: u32) {
+        let folded = zigzag(value);
+        self.write_unary(folded >> k);
+        self.write(folded as u64, k);
+    }
+
+    /// Pad with zeros up to the next byte.
+    pub(crate) fn align(&mut self) {
+        if self.pending > 0 {
+            self.write(0, 8 - self.pending);
+        }
+    }
+
+    /// Bytes written so far, the writer must be aligned.
+    pub(crate) fn bytes(&self) -> &[u8] {
+        debug_assert_eq!(self.pending, 0);
+        &self.bytes
+    }
+
+    pub(crate) fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+/// Fold the sign in the lowest bit: 0, -1, 1, -2, 2... become 0, 1, 2, 3, 4...
+pub(crate) fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+#[test]
+fn test_bit_writer() {
+    let mut writer = BitWriter::new();
+    writer.write(0b101, 3);
+    writer.write_signed(-1, 2);
+    writer.write_unary(2);
+    assert_eq!(writer.bytes.len(), 1);
+    writer.align();
+    assert_eq!(writer.bytes(), &[0b1011_1001]);
+
+    writer.write_unary(40);
+    writer.write(0xABCD_EF01, 32);
+    assert_eq!(
+        writer.into_bytes(),
+        vec![0b1011_1001, 0, 0, 0, 0, 0, 0xD5, 0xE6, 0xF7, 0x80, 0x80]
+    );
+
+    assert_eq!(
+        [0, -1, 1, -2, 2, i32::MIN].map(zigzag),
+        [0, 1, 2, 3, 4, u32::MAX]
+    );
+    let mut writer = BitWriter::new();
+    // 5 is 10 folded, 2 in unary and 0b10 in the 2 low bits
+    writer.write_rice(5, 2);
+    writer.write_rice(-1, 0);
+    assert_eq!(writer.into_bytes(), vec![0b0011_0010]);
+}
@@ -0,0 +1,27 @@
+/// CRC-8 of frame headers: polynomial x^8 + x^2 + x + 1, starting from 0.
+pub(crate) fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+            0 => crc << 1,
+            _ => crc << 1 ^ 0x07,
+        })
+    })
+}
+
+/// CRC-16 of whole frames: polynomial x^16 + x^15 + x^2 + 1, starting from 0.
+pub(crate) fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ (*byte as u16) << 8, |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => crc << 1 ^ 0x8005,
+        })
+    })
+}
+
+#[test]
+fn test_crc() {
+    // check values of CRC-8/SMBUS and CRC-16/UMTS, that share the parameters
+    assert_eq!(crc8(b"123456789"), 0xF4);
+    assert_eq!(crc16(b"123456789"), 0xFEE8);
+    assert_eq!(crc8(&[]), 0);
+}
@@ -0,0 +1,249 @@
+use super::crc::{crc16, crc8};
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, bits: u32) -> Result<u64, String> {
+        let mut value = 0;
+        for _ in 0..bits {
+            let byte = self
+                .bytes
+                .get(self.position / 8)
+                .ok_or("Unexpected end of the stream")?;
+            value = value << 1 | (byte >> (7 - self.position % 8) & 1) as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn read_signed(&mut self, bits: u32) -> Result<i64, String> {
+        let value = self.read(bits)?;
+        Ok(((value << (64 - bits)) as i64) >> (64 - bits))
+    }
+
+    fn read_rice(&mut self, k: u32) -> Result<i32, String> {
+        let mut high = 0;
+        while self.read(1)? == 0 {
+            high += 1;
+        }
+        let folded = (high << k | self.read(k)?) as u32;
+        Ok((folded >> 1) as i32 ^ -((folded & 1) as i32))
+    }
+
+    fn read_utf8(&mut self) -> Result<u64, String> {
+        let first = self.read(8)?;
+        let bytes = (first as u8).leading_ones();
+        if bytes == 0 {
+            return Ok(first);
+        }
+        let mut value = first & (0x7F >> bytes);
+        for _ in 1..bytes {
+            let byte = self.read(8)?;
+            if byte & 0xC0 != 0x80 {
+                return Err(format!("Invalid UTF-8 continuation {:#04x}", byte));
+            }
+            value = value << 6 | byte & 0x3F;
+        }
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+
+    fn byte_position(&self) -> usize {
+        self.position / 8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct StreamInfo {
+    pub min_block_size: u64,
+    pub max_block_size: u64,
+    pub min_frame_size: u64,
+    pub max_frame_size: u64,
+    pub sample_rate: u64,
+    pub channels: u64,
+    pub bits_per_sample: u32,
+    pub total_samples: u64,
+}
+
+/// Stream information and the interleaved samples of a FLAC stream.
+pub(crate) fn decode_flac(bytes: &[u8]) -> Result<(StreamInfo, Vec<i32>), String> {
+    let mut reader = BitReader { bytes, position: 0 };
+    if reader.read(32)? != u32::from_be_bytes(*b"fLaC") as u64 {
+        return Err(String::from("Missing fLaC marker"));
+    }
+    if reader.read(8)? != 0x80 || reader.read(24)? != 34 {
+        return Err(String::from("STREAMINFO must be the only metadata block"));
+    }
+    let info = StreamInfo {
+        min_block_size: reader.read(16)?,
+        max_block_size: reader.read(16)?,
+        min_frame_size: reader.read(24)?,
+        max_frame_size: reader.read(24)?,
+        sample_rate: reader.read(20)?,
+        channels: reader.read(3)? + 1,
+        bits_per_sample: reader.read(5)? as u32 + 1,
+        total_samples: reader.read(36)?,
+    };
+    reader.read(128)?;
+
+    let mut samples: Vec<i32> = Vec::new();
+    let mut number = 0;
+    while reader.byte_position() < bytes.len() {
+        let start = reader.byte_position();
+        if reader.read(15)? != 0b111_1111_1111_1100 || reader.read(1)? != 0 {
+            return Err(format!("Frame {} without sync code", number));
+        }
+        let block_size_code = reader.read(4)?;
+        let sample_rate_code = reader.read(4)?;
+        if reader.read(4)? + 1 != info.channels {
+            return Err(format!("Frame {} with other channels", number));
+        }
+        let bits_per_sample = match reader.read(3)? {
+            0b000 => info.bits_per_sample,
+            0b001 => 8,
+            0b100 => 16,
+            0b110 => 24,
+            code => return Err(format!("Sample size code {}", code)),
+        };
+        reader.read(1)?;
+        if reader.read_utf8()? != number {
+            return Err(format!("Frame {} out of order", number));
+        }
+        let block_size = match block_size_code {
+            0b0110 => reader.read(8)? as usize + 1,
+            0b0111 => reader.read(16)? as usize + 1,
+            code @ 8..=15 => 256 << (code - 8),
+            code => return Err(format!("Block size code {}", code)),
+        };
+        if (12..=14).contains(&sample_rate_code) {
+            return Err(format!("Sample rate code {}", sample_rate_code));
+        }
+        let header_crc = crc8(&bytes[start..reader.byte_position()]);
+        if reader.read(8)? != header_crc as u64 {
+            return Err(format!("Frame {} with a wrong header CRC", number));
+        }
+
+        let channels = (0..info.channels)
+            .map(|_| read_subframe(&mut reader, block_size, bits_per_sample))
+            .collect::<Result<Vec<Vec<i32>>, String>>()?;
+        reader.align();
+        let frame_crc = crc16(&bytes[start..reader.byte_position()]);
+        if reader.read(16)? != frame_crc as u64 {
+            return Err(format!("Frame {} with a wrong CRC", number));
+        }
+        for n in 0..block_size {
+            samples.extend(channels.iter().map(|channel| channel[n]));
+        }
+        number += 1;
+    }
+    Ok((info, samples))
+}
+
+fn read_subframe(
+    reader: &mut BitReader,
+    block_size: usize,
+    bits_per_sample: u32,
+) -> Result<Vec<i32>, String> {
+    if reader.read(1)? != 0 {
+        return Err(String::from("Subframe without padding"));
+    }
+    let kind = reader.read(6)?;
+    if reader.read(1)? != 0 {
+        return Err(String::from("Subframe with wasted bits"));
+    }
+    let warm_up = |order: usize, reader: &mut BitReader| {
+        (0..order)
+            .map(|_| {
+                reader
+                    .read_signed(bits_per_sample)
+                    .map(|sample| sample as i32)
+            })
+            .collect::<Result<Vec<i32>, String>>()
+    };
+    match kind {
+        0b000000 => {
+            let value = reader.read_signed(bits_per_sample)? as i32;
+            Ok(vec![value; block_size])
+        }
+        0b000001 => warm_up(block_size, reader),
+        0b001000..=0b001100 => {
+            let order = (kind & 0b111) as usize;
+            let mut samples = warm_up(order, reader)?;
+            let coefficients: &[i64] =
+                [&[][..], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]][order];
+            let residual = read_residual(reader, block_size, order)?;
+            restore(&mut samples, &residual, coefficients, 0);
+            Ok(samples)
+        }
+        0b100000..=0b111111 => {
+            let order = (kind & 0b11111) as usize + 1;
+            let mut samples = warm_up(order, reader)?;
+            let precision = reader.read(4)? as u32 + 1;
+            let shift = reader.read_signed(5)?;
+            if shift < 0 {
+                return Err(String::from("Negative LPC shift"));
+            }
+            let coefficients = (0..order)
+                .map(|_| reader.read_signed(precision))
+                .collect::<Result<Vec<i64>, String>>()?;
+            let residual = read_residual(reader, block_size, order)?;
+            restore(&mut samples, &residual, &coefficients, shift as u32);
+            Ok(samples)
+        }
+        kind => Err(format!("Reserved subframe type {:#08b}", kind)),
+    }
+}
+
+fn read_residual(
+    reader: &mut BitReader,
+    block_size: usize,
+    predictor_order: usize,
+) -> Result<Vec<i32>, String> {
+    if reader.read(2)? != 0 {
+        return Err(String::from(
+            "Residual coding method other than 4 bits Rice",
+        ));
+    }
+    let order = reader.read(4)?;
+    let partition_size = block_size >> order;
+    if partition_size << order != block_size || partition_size < predictor_order {
+        return Err(format!(
+            "Partition order {} for blocks of {}",
+            order, block_size
+        ));
+    }
+    let mut residual = Vec::with_capacity(block_size - predictor_order);
+    for partition in 0..1 << order {
+        let parameter = reader.read(4)? as u32;
+        if parameter == 15 {
+            return Err(String::from("Escaped partition"));
+        }
+        let count = match partition {
+            0 => partition_size - predictor_order,
+            _ => partition_size,
+        };
+        for _ in 0..count {
+            residual.push(reader.read_rice(parameter)?);
+        }
+    }
+    Ok(residual)
+}
+
+fn restore(samples: &mut Vec<i32>, residual: &[i32], coefficients: &[i64], shift: u32) {
+    for value in residual {
+        let n = samples.len();
+        let prediction = coefficients
+            .iter()
+            .zip(samples[..n].iter().rev())
+            .map(|(coefficient, sample)| coefficient * *sample as i64)
+            .sum::<i64>()
+            >> shift;
+        samples.push((prediction + *value as i64) as i32);
+    }
+}
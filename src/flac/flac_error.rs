@@ -0,0 +1,46 @@
+use crate::wav::SampleFormat;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum FlacError {
+    Io(io::Error),
+    /// FLAC stores integers of up to 24 bits.
+    UnsupportedSampleFormat(SampleFormat),
+    /// The sample rate of STREAMINFO has 20 bits.
+    UnsupportedSampleRate(u32),
+    /// Frames have up to 8 channels.
+    TooManyChannels(u16),
+}
+
+impl fmt::Display for FlacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlacError::Io(err) => write!(f, "Flac I/O error: {}", err),
+            FlacError::UnsupportedSampleFormat(sample_format) => write!(
+                f,
+                "Flac doesn't support {:?} samples, use u8, s16 or s24",
+                sample_format
+            ),
+            FlacError::UnsupportedSampleRate(sample_rate) => {
+                write!(
+                    f,
+                    "Flac doesn't support a sample rate of {} Hz",
+                    sample_rate
+                )
+            }
+            FlacError::TooManyChannels(channels) => {
+                write!(f, "Flac supports up to 8 channels, not {}", channels)
+            }
+        }
+    }
+}
+
+impl Error for FlacError {}
+
+impl From<io::Error> for FlacError {
+    fn from(err: io::Error) -> Self {
+        FlacError::Io(err)
+    }
+}
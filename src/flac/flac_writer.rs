@@ -0,0 +1,456 @@
+use super::bit_writer::{zigzag, BitWriter};
+use super::crc::{crc16, crc8};
+use super::lpc::{autocorrelation, fixed_residual, lpc_coefficients, lpc_residual, quantize};
+use super::FlacError;
+use crate::wav::{SampleFormat, WavSpec};
+use std::io::Write;
+
+/// Samples of every channel in a frame, but the last one.
+pub const BLOCK_SIZE: usize = 4096;
+
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_LPC_ORDER: usize = 8;
+// bits of the quantized LPC coefficients, sign included
+const LPC_PRECISION: u32 = 14;
+const MAX_PARTITION_ORDER: u32 = 8;
+// Rice parameters of 4 bits, 15 is the escape code
+const MAX_RICE_PARAMETER: u32 = 14;
+
+/// Rice coding of a residual split in 2^`order` partitions, each with its parameter.
+struct RicePartitions {
+    order: u32,
+    parameters: Vec<u32>,
+    bits: u64,
+}
+
+/// Partitions coding the residual in the fewest bits. Their bits are estimated from the
+/// sum of the folded residuals, which bounds the sum of their high parts.
+fn rice_partitions(residual: &[i32], predictor_order: usize) -> RicePartitions {
+    let block_size = residual.len() + predictor_order;
+    // partitions must have the same size, and the first one is short of the warm up samples
+    let max_order = (0..=MAX_PARTITION_ORDER)
+        .take_while(|order| {
+            block_size.is_multiple_of(1 << order) && block_size >> order > predictor_order
+        })
+        .last()
+        .unwrap_or(0);
+
+    let partition_size = block_size >> max_order;
+    let mut sums = Vec::with_capacity(1 << max_order);
+    let mut start = 0;
+    for partition in 0..1 << max_order {
+        let end = (partition + 1) * partition_size - predictor_order;
+        sums.push((
+            (end - start) as u64,
+            residual[start..end]
+                .iter()
+                .map(|value| zigzag(*value) as u64)
+                .sum::<u64>(),
+        ));
+        start = end;
+    }
+
+    let mut best: Option<RicePartitions> = None;
+    for order in (0..=max_order).rev() {
+        let mut parameters = Vec::with_capacity(sums.len());
+        let mut bits = 0;
+        for (count, sum) in sums.iter() {
+            let (parameter, parameter_bits) = (0..=MAX_RICE_PARAMETER)
+                .map(|k| (k, count * (k as u64 + 1) + (sum >> k)))
+                .min_by_key(|(_, bits)| *bits)
+                .unwrap();
+            parameters.push(parameter);
+            bits += 4 + parameter_bits;
+        }
+        if best.as_ref().is_none_or(|best| bits < best.bits) {
+            best = Some(RicePartitions {
+                order,
+                parameters,
+                bits,
+            });
+        }
+        // halve the number of partitions merging them in pairs
+        sums = sums
+            .chunks(2)
+            .map(|pair| {
+                pair.iter()
+                    .fold((0, 0), |(c, s), (count, sum)| (c + count, s + sum))
+            })
+            .collect();
+    }
+    best.unwrap()
+}
+
+fn write_residual(
+    residual: &[i32],
+    predictor_order: usize,
+    partitions: &RicePartitions,
+    out: &mut BitWriter,
+) {
+    out.write(0, 2); // coding method, Rice with 4 bits parameters
+    out.write(partitions.order as u64, 4); // partition order
+    let partition_size = (residual.len() + predictor_order) >> partitions.order;
+    let mut start = 0;
+    for (partition, parameter) in partitions.parameters.iter().enumerate() {
+        let end = (partition + 1) * partition_size - predictor_order;
+        out.write(*parameter as u64, 4); // Rice parameter
+        for value in residual[start..end].iter() {
+            out.write_rice(*value, *parameter);
+        }
+        start = end;
+    }
+}
+
+/// Predictor of a subframe, with its residual.
+enum Predictor {
+    Verbatim,
+    Fixed(usize),
+    Lpc { coefficients: Vec<i32>, shift: u32 },
+}
+
+/// Subframe of the samples of a channel in the fewest bits among a constant, the fixed
+/// predictors, linear predictors of increasing order and the verbatim samples.
+fn write_subframe(samples: &[i32], bits_per_sample: u32, out: &mut BitWriter) {
+    let bps = bits_per_sample as u64;
+    if samples.iter().all(|sample| *sample == samples[0]) {
+        out.write(0b0000_0000, 8); // padding, CONSTANT and no wasted bits
+        out.write_signed(samples[0] as i64, bits_per_sample);
+        return;
+    }
+
+    let mut best: (u64, Predictor, Vec<i32>, Option<RicePartitions>) = (
+        samples.len() as u64 * bps,
+        Predictor::Verbatim,
+        Vec::new(),
+        None,
+    );
+    for order in 0..=MAX_FIXED_ORDER.min(samples.len() - 1) {
+        if let Some(residual) = fixed_residual(samples, order) {
+            let partitions = rice_partitions(&residual, order);
+            let bits = order as u64 * bps + 6 + partitions.bits;
+            if bits < best.0 {
+                best = (bits, Predictor::Fixed(order), residual, Some(partitions));
+            }
+        }
+    }
+    if samples.len() > MAX_LPC_ORDER {
+        for predictor in lpc_coefficients(&autocorrelation(samples, MAX_LPC_ORDER)) {
+            let order = predictor.len();
+            let (coefficients, shift) = quantize(&predictor, LPC_PRECISION);
+            if let Some(residual) = lpc_residual(samples, &coefficients, shift) {
+                let partitions = rice_partitions(&residual, order);
+                let bits = order as u64 * (bps + LPC_PRECISION as u64) + 9 + 6 + partitions.bits;
+                if bits < best.0 {
+                    best = (
+                        bits,
+                        Predictor::Lpc {
+                            coefficients,
+                            shift,
+                        },
+                        residual,
+                        Some(partitions),
+                    );
+                }
+            }
+        }
+    }
+
+    let (_, predictor, residual, partitions) = best;
+    match predictor {
+        Predictor::Verbatim => {
+            out.write(0b0000_0010, 8); // padding, VERBATIM and no wasted bits
+            for sample in samples {
+                out.write_signed(*sample as i64, bits_per_sample);
+            }
+            return;
+        }
+        Predictor::Fixed(order) => {
+            out.write((0b001000 | order as u64) << 1, 8); // padding, FIXED of order
+            for sample in &samples[..order] {
+                out.write_signed(*sample as i64, bits_per_sample);
+            }
+        }
+        Predictor::Lpc {
+            coefficients,
+            shift,
+        } => {
+            let order = coefficients.len();
+            out.write((0b100000 | (order as u64 - 1)) << 1, 8); // padding, LPC of order
+            for sample in &samples[..order] {
+                out.write_signed(*sample as i64, bits_per_sample);
+            }
+            out.write(LPC_PRECISION as u64 - 1, 4); // precision of the coefficients
+            out.write(shift as u64, 5); // shift of the prediction
+            for coefficient in coefficients {
+                out.write_signed(coefficient as i64, LPC_PRECISION);
+            }
+        }
+    }
+    let order = samples.len() - residual.len();
+    write_residual(&residual, order, &partitions.unwrap(), out);
+}
+
+/// Code of the sample rate in the frame header, 0 reads it from STREAMINFO.
+fn sample_rate_code(sample_rate: u32) -> u64 {
+    match sample_rate {
+        88200 => 1,
+        176400 => 2,
+        192000 => 3,
+        8000 => 4,
+        16000 => 5,
+        22050 => 6,
+        24000 => 7,
+        32000 => 8,
+        44100 => 9,
+        48000 => 10,
+        96000 => 11,
+        _ => 0,
+    }
+}
+
+/// Code of the bits per sample in the frame header.
+fn sample_size_code(bits_per_sample: u32) -> u64 {
+    match bits_per_sample {
+        8 => 0b001,
+        12 => 0b010,
+        16 => 0b100,
+        20 => 0b101,
+        24 => 0b110,
+        _ => 0b000,
+    }
+}
+
+/// Number coded like the characters of UTF-8, extended up to 36 bits.
+fn write_utf8(value: u64, out: &mut BitWriter) {
+    if value < 0x80 {
+        out.write(value, 8);
+        return;
+    }
+    // the first byte holds 7 - bytes bits, the following ones 6 bits each
+    let mut bytes = 2;
+    while value >> (5 * bytes + 1) != 0 {
+        bytes += 1;
+    }
+    let continuation = 6 * (bytes - 1);
+    out.write(0xFF << (8 - bytes) | value >> continuation, 8);
+    for shift in (0..continuation).step_by(6).rev() {
+        out.write(0x80 | (value >> shift & 0x3F), 8);
+    }
+}
+
+/// Frame of a block of samples, the same number for every channel.
+fn encode_frame(
+    number: u64,
+    channels: &[Vec<i32>],
+    sample_rate: u32,
+    bits_per_sample: u32,
+) -> Vec<u8> {
+    let block_size = channels[0].len();
+    let block_size_code = match block_size {
+        BLOCK_SIZE => 0b1100,
+        // the last block writes its size after the frame number
+        size if size <= 256 => 0b0110,
+        _ => 0b0111,
+    };
+
+    let mut out = BitWriter::new();
+    out.write(0b11_1111_1111_1110, 14); // sync code
+    out.write(0, 1); // reserved
+    out.write(0, 1); // fixed block size
+    out.write(block_size_code, 4);
+    out.write(sample_rate_code(sample_rate), 4);
+    out.write(channels.len() as u64 - 1, 4); // independent channels
+    out.write(sample_size_code(bits_per_sample), 3);
+    out.write(0, 1); // reserved
+    write_utf8(number, &mut out);
+    match block_size_code {
+        0b0110 => out.write(block_size as u64 - 1, 8),
+        0b0111 => out.write(block_size as u64 - 1, 16),
+        _ => {}
+    }
+    out.write(crc8(out.bytes()) as u64, 8);
+
+    for samples in channels {
+        write_subframe(samples, bits_per_sample, &mut out);
+    }
+    out.align();
+    let crc = crc16(out.bytes());
+    out.write(crc as u64, 16);
+    out.into_bytes()
+}
+
+/// Bits per sample of the spec, if FLAC can store its samples.
+pub fn check_spec(spec: &WavSpec) -> Result<u32, FlacError> {
+    let bits_per_sample = match spec.sample_format {
+        SampleFormat::U8 | SampleFormat::S16 | SampleFormat::S24 => {
+            spec.sample_format.bits_per_sample() as u32
+        }
+        sample_format => return Err(FlacError::UnsupportedSampleFormat(sample_format)),
+    };
+    if spec.sample_rate == 0 || spec.sample_rate >= 1 << 20 {
+        return Err(FlacError::UnsupportedSampleRate(spec.sample_rate));
+    }
+    if spec.channels == 0 || spec.channels > 8 {
+        return Err(FlacError::TooManyChannels(spec.channels));
+    }
+    Ok(bits_per_sample)
+}
+
+/// Write samples between -1 and 1 as a FLAC stream, compressed without losses.
+/// Blocks are encoded and written as the samples come, so the sizes of the frames
+/// are left unknown in STREAMINFO.
+///
+/// FLAC stores integers, the samples are quantized as [SampleFormat] does for WAV,
+/// 8, 16 or 24 bits.
+pub fn write_flac<T>(samples: T, spec: &WavSpec, writer: &mut dyn Write) -> Result<(), FlacError>
+where
+    T: IntoIterator<Item = f32>,
+    T::IntoIter: ExactSizeIterator,
+{
+    let bits_per_sample = check_spec(spec)?;
+    let mut samples = samples.into_iter();
+    let channels = spec.channels as usize;
+    let frames = samples.len() / channels;
+
+    let mut header = BitWriter::new();
+    header.write(u32::from_be_bytes(*b"fLaC") as u64, 32);
+    header.write(1, 1); // last metadata block
+    header.write(0, 7); // STREAMINFO
+    header.write(34, 24); // length of the block
+    header.write(BLOCK_SIZE as u64, 16); // minimum block size
+    header.write(BLOCK_SIZE as u64, 16); // maximum block size
+    header.write(0, 24); // minimum frame size, unknown
+    header.write(0, 24); // maximum frame size, unknown
+    header.write(spec.sample_rate as u64, 20);
+    header.write(channels as u64 - 1, 3); // channels
+    header.write(bits_per_sample as u64 - 1, 5); // bits per sample
+    header.write((frames as u64) >> 32, 4); // total samples of a channel
+    header.write(frames as u64 & 0xFFFF_FFFF, 32);
+    // the MD5 of the samples is optional, all zeros when it isn't computed
+    for _ in 0..4 {
+        header.write(0, 32);
+    }
+    writer.write_all(header.bytes())?;
+
+    let mut block: Vec<Vec<i32>> = vec![Vec::with_capacity(BLOCK_SIZE); channels];
+    let mut remaining = frames;
+    let mut number = 0;
+    while remaining > 0 {
+        let block_size = remaining.min(BLOCK_SIZE);
+        block.iter_mut().for_each(|channel| channel.clear());
+        for (n, sample) in samples.by_ref().take(block_size * channels).enumerate() {
+            block[n % channels].push(spec.sample_format.quantize(sample));
+        }
+        writer.write_all(&encode_frame(
+            number,
+            &block,
+            spec.sample_rate,
+            bits_per_sample,
+        ))?;
+        remaining -= block_size;
+        number += 1;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_utf8() {
+    let utf8 = |value: u64| {
+        let mut out = BitWriter::new();
+        write_utf8(value, &mut out);
+        out.into_bytes()
+    };
+    assert_eq!(utf8(0x7F), vec![0x7F]);
+    assert_eq!(utf8(0x80), vec![0xC2, 0x80]);
+    assert_eq!(utf8(0x7FF), vec![0xDF, 0xBF]);
+    assert_eq!(utf8(0x800), vec![0xE0, 0xA0, 0x80]);
+    assert_eq!(
+        utf8((1 << 36) - 1),
+        vec![0xFE, 0xBF, 0xBF, 0xBF, 0xBF, 0xBF, 0xBF]
+    );
+}
+
+#[test]
+fn test_write_flac() {
+    use super::flac_decoder::decode_flac;
+    use crate::audio::AudioSettings;
+    use crate::morse_alphabet::{O, S, SPACE};
+
+    let round_trip = |data: &[f32], spec: WavSpec| {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_flac(data.iter().copied(), &spec, &mut bytes).unwrap();
+        let (info, samples) = decode_flac(&bytes).unwrap();
+        let expected = data[..data.len() / spec.channels as usize * spec.channels as usize]
+            .iter()
+            .map(|sample| spec.sample_format.quantize(*sample))
+            .collect::<Vec<i32>>();
+        assert_eq!(samples, expected);
+        assert_eq!(info.sample_rate, spec.sample_rate as u64);
+        assert_eq!(info.channels, spec.channels as u64);
+        assert_eq!(
+            info.bits_per_sample,
+            spec.sample_format.bits_per_sample() as u32
+        );
+        assert_eq!(
+            info.total_samples,
+            (expected.len() / spec.channels as usize) as u64
+        );
+        assert_eq!((info.min_frame_size, info.max_frame_size), (0, 0));
+        bytes.len()
+    };
+
+    // silence and a pure tone compress to a fraction of the samples
+    let settings = AudioSettings::default();
+    let morse = settings.render([S, O, S, SPACE, S, O, S]);
+    let size = round_trip(
+        &morse,
+        WavSpec::mono(settings.sample_rate, SampleFormat::S16),
+    );
+    assert!(size * 8 < morse.len() * 2);
+
+    // noise is stored verbatim, the last block is shorter
+    let mut seed: u32 = 1;
+    let noise = (0..2 * 5000)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as f32 / 32768.0 - 1.0
+        })
+        .collect::<Vec<f32>>();
+    let stereo = WavSpec {
+        channels: 2,
+        sample_rate: 11025,
+        sample_format: SampleFormat::U8,
+    };
+    round_trip(&noise, stereo);
+    round_trip(&noise[..201], stereo);
+
+    // blocks shorter than 256 samples and longer ones write their size differently
+    let sine = (0..300)
+        .map(|n| (n as f32 * 0.05).sin() * 0.9)
+        .collect::<Vec<f32>>();
+    round_trip(&sine, WavSpec::mono(48000, SampleFormat::S24));
+    round_trip(&sine[..100], WavSpec::mono(48000, SampleFormat::S24));
+    round_trip(&[0.5], WavSpec::mono(44100, SampleFormat::S16));
+    round_trip(&[], WavSpec::mono(44100, SampleFormat::S16));
+
+    // nothing is written for unsupported specs
+    let mut bytes: Vec<u8> = Vec::new();
+    assert!(matches!(
+        write_flac(
+            sine.iter().copied(),
+            &WavSpec::mono(44100, SampleFormat::F32),
+            &mut bytes
+        ),
+        Err(FlacError::UnsupportedSampleFormat(SampleFormat::F32))
+    ));
+    assert!(bytes.is_empty());
+    assert!(matches!(
+        write_flac(
+            sine.iter().copied(),
+            &WavSpec::mono(1 << 20, SampleFormat::S16),
+            &mut Vec::new()
+        ),
+        Err(FlacError::UnsupportedSampleRate(_))
+    ));
+}
@@ -0,0 +1,138 @@
+/// Residual of the fixed polynomial predictor of `order`, from 0 to 4, for the samples
+/// after the first `order` ones. None if a residual doesn't fit in 32 bits.
+pub(crate) fn fixed_residual(samples: &[i32], order: usize) -> Option<Vec<i32>> {
+    const COEFFICIENTS: [&[i64]; 5] = [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+    residual(samples, COEFFICIENTS[order], 0)
+}
+
+/// Residual of the linear predictor with quantized `coefficients`, the first one
+/// multiplying the previous sample, whose sum is shifted right by `shift`.
+pub(crate) fn lpc_residual(samples: &[i32], coefficients: &[i32], shift: u32) -> Option<Vec<i32>> {
+    let coefficients = coefficients
+        .iter()
+        .map(|coefficient| *coefficient as i64)
+        .collect::<Vec<i64>>();
+    residual(samples, &coefficients, shift)
+}
+
+fn residual(samples: &[i32], coefficients: &[i64], shift: u32) -> Option<Vec<i32>> {
+    let order = coefficients.len();
+    (order..samples.len())
+        .map(|n| {
+            let prediction = coefficients
+                .iter()
+                .zip(samples[..n].iter().rev())
+                .map(|(coefficient, sample)| coefficient * *sample as i64)
+                .sum::<i64>()
+                >> shift;
+            i32::try_from(samples[n] as i64 - prediction).ok()
+        })
+        .collect()
+}
+
+/// Autocorrelation of the samples for the lags up to `max_lag`, under a Welch window
+/// that fades the edges of the block.
+pub(crate) fn autocorrelation(samples: &[i32], max_lag: usize) -> Vec<f64> {
+    let half = samples.len() as f64 / 2.0;
+    let windowed = samples
+        .iter()
+        .enumerate()
+        .map(|(n, sample)| {
+            let distance = (n as f64 + 0.5 - half) / half;
+            *sample as f64 * (1.0 - distance * distance)
+        })
+        .collect::<Vec<f64>>();
+    (0..=max_lag)
+        .map(|lag| {
+            windowed[lag..]
+                .iter()
+                .zip(windowed.iter())
+                .map(|(a, b)| a * b)
+                .sum()
+        })
+        .collect()
+}
+
+/// Coefficients of the linear predictors of every order from 1 to the lags of the
+/// autocorrelation, with the Levinson-Durbin recursion. The recursion stops early
+/// when the prediction is already perfect.
+pub(crate) fn lpc_coefficients(autocorrelation: &[f64]) -> Vec<Vec<f64>> {
+    let mut predictors: Vec<Vec<f64>> = Vec::new();
+    let mut coefficients: Vec<f64> = Vec::new();
+    let mut error = autocorrelation[0];
+    for order in 1..autocorrelation.len() {
+        if error <= 0.0 {
+            break;
+        }
+        let reflection = (autocorrelation[order]
+            - coefficients
+                .iter()
+                .enumerate()
+                .map(|(j, coefficient)| coefficient * autocorrelation[order - 1 - j])
+                .sum::<f64>())
+            / error;
+        let previous = coefficients.clone();
+        for (j, coefficient) in coefficients.iter_mut().enumerate() {
+            *coefficient -= reflection * previous[order - 2 - j];
+        }
+        coefficients.push(reflection);
+        error *= 1.0 - reflection * reflection;
+        predictors.push(coefficients.clone());
+    }
+    predictors
+}
+
+/// Coefficients as integers of `precision` bits, sign included, and the shift that
+/// scales them back. The rounding error of each coefficient is carried to the next one.
+pub(crate) fn quantize(coefficients: &[f64], precision: u32) -> (Vec<i32>, u32) {
+    let max = coefficients
+        .iter()
+        .fold(0.0_f64, |max, coefficient| max.max(coefficient.abs()));
+    let limit = (1 << (precision - 1)) - 1;
+    if max <= 0.0 {
+        return (vec![0; coefficients.len()], 0);
+    }
+    // the largest coefficient takes all the bits but the sign
+    let shift = (precision as i32 - 1 - (max.log2().floor() as i32 + 1)).clamp(0, 15) as u32;
+    let mut error = 0.0;
+    let quantized = coefficients
+        .iter()
+        .map(|coefficient| {
+            let scaled = coefficient * (1 << shift) as f64 + error;
+            let rounded = scaled.round().clamp(-(limit as f64) - 1.0, limit as f64);
+            error = scaled - rounded;
+            rounded as i32
+        })
+        .collect();
+    (quantized, shift)
+}
+
+#[test]
+fn test_predictors() {
+    let ramp = (0..20).map(|n| n * n).collect::<Vec<i32>>();
+    assert_eq!(fixed_residual(&ramp, 0).unwrap(), ramp);
+    // the second difference of a parabola is constant, the third is zero
+    assert!(fixed_residual(&ramp, 2).unwrap().iter().all(|r| *r == 2));
+    assert!(fixed_residual(&ramp, 3).unwrap().iter().all(|r| *r == 0));
+    assert_eq!(fixed_residual(&ramp, 4).unwrap().len(), 16);
+    assert_eq!(fixed_residual(&[i32::MIN, i32::MAX], 1), None);
+
+    // a sine follows s[n] = 2 cos(w) s[n-1] - s[n-2]
+    let w = 2.0 * std::f64::consts::PI * 440.0 / 44100.0;
+    let sine = (0..4096)
+        .map(|n| (10000.0 * (w * n as f64).sin()).round() as i32)
+        .collect::<Vec<i32>>();
+    let predictors = lpc_coefficients(&autocorrelation(&sine, 8));
+    assert_eq!(predictors.len(), 8);
+    assert!((predictors[1][0] - 2.0 * w.cos()).abs() < 1e-3);
+    assert!((predictors[1][1] + 1.0).abs() < 1e-3);
+
+    let (coefficients, shift) = quantize(&predictors[1], 14);
+    assert_eq!(shift, 12);
+    assert!(coefficients.iter().all(|c| c.abs() < 1 << 13));
+    let residual = lpc_residual(&sine, &coefficients, shift).unwrap();
+    assert!(residual.iter().all(|r| r.abs() <= 8));
+
+    assert_eq!(quantize(&[0.0, 0.0], 12), (vec![0, 0], 0));
+    assert_eq!(quantize(&[0.5, -0.25], 4), (vec![4, -2], 3));
+}
@@ -0,0 +1,10 @@
+mod bit_writer;
+mod crc;
+mod flac_error;
+mod flac_writer;
+mod lpc;
+// decoder checking the structure of the streams written, for the tests
+#[cfg(test)]
+mod flac_decoder;
+pub use flac_error::FlacError;
+pub use flac_writer::{check_spec, write_flac, BLOCK_SIZE};
@@ -1,17 +1,20 @@
+pub mod audio;
+pub mod decoder;
+pub mod flac;
+pub mod lint;
+pub mod midi;
 pub mod parser;
 pub mod polyphonia;
+pub mod subtitles;
+pub mod timing;
 pub mod translator;
 pub mod utils;
 pub mod wav;
 
-use polyphonia::{notable_notes, Amplitude};
+use audio::AudioSettings;
 use std::str::FromStr;
 
-const DOT_DURATION: f32 = 0.1;
-const LINE_DURATION: f32 = DOT_DURATION * 2.0;
-const SLASH_DURATION: f32 = DOT_DURATION * 4.0;
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Letter<'a>(&'a str, &'a str);
 
 impl<'a> Letter<'a> {
@@ -45,26 +48,11 @@ impl<'a> Letter<'a> {
         output
     }
 
-    pub fn concat_audio<T: Iterator<Item = Letter<'a>>>(args: T) -> Vec<i16> {
-        let mut output: Vec<i16> = Vec::new();
-        for ch in args
-            .map(|x| -> &str {
-                let Self(_, y) = x;
-                y
-            })
-            .flat_map(|x| x.chars())
-        {
-            let chunk = match ch {
-                '.' => notable_notes::A4.audio_wave(DOT_DURATION, &Amplitude::Medium),
-                '-' => notable_notes::A4.audio_wave(LINE_DURATION, &Amplitude::Medium),
-                '/' => notable_notes::A4.audio_wave(SLASH_DURATION, &Amplitude::Silent),
-                _ => Vec::new(),
-            };
-            output.extend_from_slice(&chunk);
-            output
-                .extend_from_slice(&notable_notes::G0.audio_wave(DOT_DURATION, &Amplitude::Silent))
-        }
-        output
+    pub fn concat_audio<T: Iterator<Item = Letter<'a>>>(
+        args: T,
+        settings: &AudioSettings,
+    ) -> Vec<f32> {
+        settings.render(args)
     }
 }
 
@@ -108,6 +96,12 @@ pub mod morse_alphabet {
     pub const NINE: Letter = Letter("9", "----.");
     pub const ZERO: Letter = Letter("0", "-----");
     pub const SPACE: Letter = Letter(" ", "/");
+
+    /// Every letter and digit of the alphabet, without the word separator.
+    pub const ALPHABET: [Letter; 36] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, ONE, TWO,
+        THREE, FOUR, FIVE, SIX, SEVEN, EIGHT, NINE, ZERO,
+    ];
 }
 
 impl FromStr for Letter<'_> {
@@ -163,3 +157,103 @@ impl PartialEq for Letter<'_> {
         human1 == human2 && morse1 == morse2
     }
 }
+
+#[test]
+fn test_audio_gaps() {
+    use decoder::{KeyInterval, TimingClassifier};
+    use timing::MorseTiming;
+
+    // lengths in units of the tones (positive) and silences (negative) of the audio
+    fn keyed_units(text: &str, timing: &MorseTiming) -> Vec<i32> {
+        let letters = text
+            .chars()
+            .map(|ch| Letter::from_str(&ch.to_string()).unwrap());
+        let settings = AudioSettings {
+            timing: *timing,
+            ..Default::default()
+        };
+        let samples = Letter::concat_audio(letters, &settings);
+        let unit_samples = timing.dot * settings.sample_rate as f64;
+
+        // the sine crosses zero inside tones, only long runs of zeros are silence
+        let mut runs: Vec<(bool, usize)> = Vec::new();
+        let mut zeros = 0;
+        for sample in samples {
+            if sample == 0.0 {
+                zeros += 1;
+                continue;
+            }
+            match zeros > 10 {
+                true => runs.push((false, zeros)),
+                false => {
+                    if let Some((true, length)) = runs.last_mut() {
+                        *length += zeros
+                    }
+                }
+            }
+            zeros = 0;
+            match runs.last_mut() {
+                Some((true, length)) => *length += 1,
+                _ => runs.push((true, 1)),
+            }
+        }
+        assert!(zeros < 10, "trailing silence");
+        runs.into_iter()
+            .map(|(tone, length)| {
+                let units = f64::round(length as f64 / unit_samples) as i32;
+                match tone {
+                    true => units,
+                    false => -units,
+                }
+            })
+            .collect()
+    }
+
+    let timing = MorseTiming::from_wpm(20.0);
+    assert_eq!(keyed_units("et", &timing), vec![1, -3, 3]);
+    assert_eq!(keyed_units("a", &timing), vec![1, -1, 3]);
+    assert_eq!(
+        keyed_units("paris paris", &timing),
+        vec![
+            1, -1, 3, -1, 3, -1, 1, -3, // p
+            1, -1, 3, -3, // a
+            1, -1, 3, -1, 1, -3, // r
+            1, -1, 1, -3, // i
+            1, -1, 1, -1, 1, -7, // s
+            1, -1, 3, -1, 3, -1, 1, -3, // p
+            1, -1, 3, -3, // a
+            1, -1, 3, -1, 1, -3, // r
+            1, -1, 1, -3, // i
+            1, -1, 1, -1, 1, // s
+        ]
+    );
+
+    // Farnsworth gaps are stretched to the effective speed
+    let farnsworth = MorseTiming::farnsworth(20.0, 10.0);
+    let units = keyed_units("e e", &farnsworth);
+    let word_gap = -units[1] as f64 * farnsworth.dot;
+    assert_eq!(units[0], 1);
+    assert!((word_gap - farnsworth.word_gap).abs() < farnsworth.dot);
+
+    // the timing of the audio is decoded back to the text
+    let units = keyed_units("sos", &timing);
+    let mut start_ms = 0_f64;
+    let intervals = units
+        .iter()
+        .map(|units| {
+            let duration_ms = units.abs() as f64 * timing.dot * 1000.0;
+            start_ms += duration_ms;
+            KeyInterval {
+                mark: *units > 0,
+                start_ms: start_ms - duration_ms,
+                duration_ms,
+            }
+        })
+        .collect::<Vec<KeyInterval>>();
+    assert_eq!(
+        TimingClassifier::from_intervals(&intervals)
+            .decode(&intervals)
+            .unwrap(),
+        vec![morse_alphabet::S, morse_alphabet::O, morse_alphabet::S]
+    );
+}
@@ -0,0 +1,206 @@
+use crate::{morse_alphabet, Letter};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintKind {
+    UnknownCode(String),
+    IllegalCharacter(char),
+    CodeTooLong(String),
+    InconsistentSeparator { found: String, expected: String },
+    DoubledWordSeparator,
+}
+
+impl LintKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintKind::InconsistentSeparator { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintKind::UnknownCode(code) => write!(f, "unknown code {:?}", code),
+            LintKind::IllegalCharacter(ch) => write!(f, "illegal character {:?}", ch),
+            LintKind::CodeTooLong(code) => write!(
+                f,
+                "code {:?} is longer than {} elements",
+                code,
+                max_code_length()
+            ),
+            LintKind::InconsistentSeparator { found, expected } => write!(
+                f,
+                "separator {:?} differs from the previous {:?}",
+                found, expected
+            ),
+            LintKind::DoubledWordSeparator => write!(f, "doubled word separator"),
+        }
+    }
+}
+
+/// Problem found in morse text, lines and columns start from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub line: usize,
+    pub column: usize,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line,
+            self.column,
+            self.kind.severity(),
+            self.kind
+        )
+    }
+}
+
+fn max_code_length() -> usize {
+    morse_alphabet::ALPHABET
+        .iter()
+        .map(|letter| {
+            let Letter(_, morse) = letter;
+            morse.len()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Validate morse text, where letters are separated by whitespace and words by `/`.
+///
+/// Separators are expected to be written the same way in the whole text,
+/// the first letter and word separators found are used as reference.
+pub fn check<S: AsRef<str>>(lines: &[S]) -> Vec<LintIssue> {
+    let max_length = max_code_length();
+    let mut issues: Vec<LintIssue> = Vec::new();
+    let mut letter_separator: Option<String> = None;
+    let mut word_separator: Option<String> = None;
+
+    for (line_index, line) in lines.iter().enumerate() {
+        let line = line.as_ref();
+        let mut issue = |column: usize, kind: LintKind| {
+            issues.push(LintIssue {
+                line: line_index + 1,
+                column: column + 1,
+                kind,
+            })
+        };
+
+        // split the line in alternating codes and separators, keeping the columns
+        let chars = line.chars().collect::<Vec<char>>();
+        let is_separator = |ch: char| ch.is_whitespace() || ch == '/';
+        let mut tokens: Vec<(usize, String, bool)> = Vec::new();
+        for (column, &ch) in chars.iter().enumerate() {
+            match tokens.last_mut() {
+                Some((_, token, separator)) if *separator == is_separator(ch) => token.push(ch),
+                _ => tokens.push((column, ch.to_string(), is_separator(ch))),
+            }
+        }
+
+        let token_count = tokens.len();
+        for (i, (column, token, separator)) in tokens.into_iter().enumerate() {
+            if !separator {
+                if let Some((offset, illegal)) = token
+                    .chars()
+                    .enumerate()
+                    .find(|(_, ch)| *ch != '.' && *ch != '-')
+                {
+                    issue(column + offset, LintKind::IllegalCharacter(illegal));
+                } else if token.len() > max_length {
+                    issue(column, LintKind::CodeTooLong(token));
+                } else if Letter::from_str(&token).is_err() {
+                    issue(column, LintKind::UnknownCode(token));
+                }
+                continue;
+            }
+
+            let slashes = token.match_indices('/').collect::<Vec<(usize, &str)>>();
+            if slashes.len() > 1 {
+                issue(column + slashes[1].0, LintKind::DoubledWordSeparator);
+            }
+            // leading and trailing separators don't sit between two codes
+            if i == 0 || i == token_count - 1 {
+                continue;
+            }
+            let reference = match slashes.is_empty() {
+                true => &mut letter_separator,
+                false => &mut word_separator,
+            };
+            match reference {
+                None => *reference = Some(token),
+                Some(expected) if *expected != token => issue(
+                    column,
+                    LintKind::InconsistentSeparator {
+                        found: token,
+                        expected: expected.clone(),
+                    },
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+    issues.sort_by_key(|issue| (issue.line, issue.column));
+    issues
+}
+
+#[test]
+fn test_check() {
+    let issues = check(&[
+        ".... . .-.. .-.. --- / .-- --- .-. .-.. -..",
+        ".-.- ..x / ......",
+        "... --- ...  / / -.-.",
+        "-.-. --.-   -.. .",
+    ]);
+    let found = issues
+        .iter()
+        .map(|issue| (issue.line, issue.column, issue.kind.clone()))
+        .collect::<Vec<(usize, usize, LintKind)>>();
+    assert_eq!(
+        found,
+        vec![
+            (2, 1, LintKind::UnknownCode(".-.-".into())),
+            (2, 8, LintKind::IllegalCharacter('x')),
+            (2, 12, LintKind::CodeTooLong("......".into())),
+            (
+                3,
+                12,
+                LintKind::InconsistentSeparator {
+                    found: "  / / ".into(),
+                    expected: " / ".into(),
+                }
+            ),
+            (3, 16, LintKind::DoubledWordSeparator),
+            (
+                4,
+                10,
+                LintKind::InconsistentSeparator {
+                    found: "   ".into(),
+                    expected: " ".into(),
+                }
+            ),
+        ]
+    );
+    assert_eq!(issues[0].to_string(), "2:1: error: unknown code \".-.-\"");
+}
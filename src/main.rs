@@ -4,12 +4,49 @@ use morse_traducer::translator::{MorseTranslator, StreamedMorseTranslator};
 
 fn main() {
     let args = MorseArgs::parse();
-    StreamedMorseTranslator::default()
-        .out_file(&args.out_file)
+    let mut translator = StreamedMorseTranslator::default();
+    if let Some(dictionary) = &args.dictionary {
+        translator.dictionary_file(dictionary);
+    }
+    if let Some(title) = &args.title {
+        translator.title(title);
+    }
+    if let Some(subtitles) = &args.subtitles {
+        translator.subtitle_file(subtitles);
+    }
+    if let Some(subtitle_format) = args.subtitle_format {
+        translator.subtitle_format(subtitle_format);
+    }
+    translator.wpm(args.wpm);
+    if let Some(effective_wpm) = args.farnsworth {
+        translator.effective_wpm(effective_wpm);
+    }
+    translator.out_file(&args.out_file);
+    if let Some(format) = args.format {
+        translator.audio_format(format);
+    }
+    translator
         .in_file(&args.in_file)
         .traduction_type(args.traduction_type)
+        .tone(args.tone)
+        .volume(args.volume)
+        .envelope(args.envelope, args.rise_time / 1000.0)
+        .waveform(args.waveform)
+        .sample_rate(args.sample_rate)
+        .sample_format(args.sample_format)
+        .subtitle_granularity(args.subtitle_granularity)
+        .tempo(args.tempo)
+        .velocity(args.velocity)
+        .decoded_format(args.decoded_format)
+        .confidence_marker(&args.confidence_marker)
+        .confidence_threshold(args.confidence_threshold)
+        .segment_candidates(args.segment)
+        .correct(args.correct)
         .translate(args.morse_command)
-        .unwrap();
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
 }
 
 #[test]
@@ -0,0 +1,256 @@
+use crate::timing::KeyingElement;
+use std::io::{self, Write};
+
+/// Ticks of a quarter note, the time division of the files written.
+pub const TICKS_PER_QUARTER: u16 = 480;
+
+/// A dot of morse lasts a sixteenth note.
+pub const TICKS_PER_DOT: u32 = TICKS_PER_QUARTER as u32 / 4;
+
+/// Event of a MIDI track, channel events are on the first channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiEvent {
+    NoteOn {
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        note: u8,
+    },
+    /// Microseconds of a quarter note.
+    Tempo(u32),
+    EndOfTrack,
+}
+
+/// Microseconds of the slowest quarter note, the tempo has 24 bits.
+pub const MAX_TEMPO_MICROS: u32 = 0xFF_FFFF;
+
+/// Largest variable-length quantity, of 4 bytes.
+pub const MAX_VLQ: u32 = 0x0FFF_FFFF;
+
+impl MidiEvent {
+    fn write(&self, buffer: &mut Vec<u8>) -> io::Result<()> {
+        match *self {
+            MidiEvent::NoteOn { note, velocity } => {
+                buffer.extend_from_slice(&[0x90, note, velocity])
+            }
+            MidiEvent::NoteOff { note } => buffer.extend_from_slice(&[0x80, note, 0x40]),
+            MidiEvent::Tempo(micros) if micros > MAX_TEMPO_MICROS => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Tempo of {} µs per quarter note exceeds 24 bits", micros),
+                ))
+            }
+            MidiEvent::Tempo(micros) => {
+                buffer.extend_from_slice(&[0xFF, 0x51, 0x03]);
+                buffer.extend_from_slice(&micros.to_be_bytes()[1..]);
+            }
+            MidiEvent::EndOfTrack => buffer.extend_from_slice(&[0xFF, 0x2F, 0x00]),
+        }
+        Ok(())
+    }
+}
+
+/// Append a variable-length quantity: 7 bits per byte, most significant first, every
+/// byte but the last has the top bit set. Values are limited to 28 bits.
+pub fn write_vlq(value: u32, buffer: &mut Vec<u8>) -> io::Result<()> {
+    if value > MAX_VLQ {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Variable-length quantity {} exceeds 28 bits", value),
+        ));
+    }
+    let mut shift = 21;
+    while shift > 0 && value >> shift == 0 {
+        shift -= 7;
+    }
+    while shift > 0 {
+        buffer.push(0x80 | (value >> shift & 0x7F) as u8);
+        shift -= 7;
+    }
+    buffer.push((value & 0x7F) as u8);
+    Ok(())
+}
+
+/// Write a format 0 Standard MIDI File, with a single track of events preceded by
+/// the ticks elapsed since the previous one.
+pub fn write_smf(events: &[(u32, MidiEvent)], writer: &mut dyn Write) -> io::Result<()> {
+    let mut track: Vec<u8> = Vec::new();
+    for (delta, event) in events {
+        write_vlq(*delta, &mut track)?;
+        event.write(&mut track)?;
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(track.len() + 22);
+    bytes.extend_from_slice(b"MThd");
+    bytes.extend_from_slice(&6_u32.to_be_bytes()); // header chunk size
+    bytes.extend_from_slice(&0_u16.to_be_bytes()); // format
+    bytes.extend_from_slice(&1_u16.to_be_bytes()); // number of tracks
+    bytes.extend_from_slice(&TICKS_PER_QUARTER.to_be_bytes()); // time division
+    bytes.extend_from_slice(b"MTrk");
+    bytes.extend_from_slice(&(track.len() as u32).to_be_bytes()); // track chunk size
+    bytes.extend_from_slice(&track);
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Settings of the MIDI rendering of morse code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiSettings {
+    /// Quarter notes per minute.
+    pub bpm: f32,
+    pub note: u8,
+    pub velocity: u8,
+}
+
+impl MidiSettings {
+    /// Tempo at which a dot of `dot` seconds lasts a sixteenth note, so that the
+    /// rhythm plays at the speed of the audio.
+    pub fn bpm_of_dot(dot: f64) -> f32 {
+        (60.0 / (4.0 * dot)) as f32
+    }
+}
+
+/// Events playing the tones of the keying as notes.
+///
+/// Durations are measured in dots of `dot` seconds, the times are rounded to the tick
+/// from the start of the track so that the rounding errors don't add up.
+/// Fails when the ticks between two events don't fit in a delta time.
+pub fn morse_events(
+    keying: &[KeyingElement],
+    dot: f64,
+    settings: &MidiSettings,
+) -> io::Result<Vec<(u32, MidiEvent)>> {
+    let delta = |ticks: u64| {
+        u32::try_from(ticks)
+            .ok()
+            .filter(|ticks| *ticks <= MAX_VLQ)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Delta time of {} ticks exceeds 28 bits", ticks),
+                )
+            })
+    };
+    let mut events = vec![(
+        0,
+        MidiEvent::Tempo((60_000_000.0 / settings.bpm).round() as u32),
+    )];
+    let mut time: f64 = 0.0;
+    let mut last_tick: u64 = 0;
+    for element in keying {
+        let start = (time / dot * TICKS_PER_DOT as f64).round() as u64;
+        time += element.duration;
+        if !element.tone {
+            continue;
+        }
+        let end = (time / dot * TICKS_PER_DOT as f64).round() as u64;
+        events.push((
+            delta(start - last_tick)?,
+            MidiEvent::NoteOn {
+                note: settings.note,
+                velocity: settings.velocity,
+            },
+        ));
+        events.push((
+            delta(end - start)?,
+            MidiEvent::NoteOff {
+                note: settings.note,
+            },
+        ));
+        last_tick = end;
+    }
+    events.push((0, MidiEvent::EndOfTrack));
+    Ok(events)
+}
+
+#[test]
+fn test_vlq() {
+    let vlq = |value: u32| {
+        let mut buffer = Vec::new();
+        write_vlq(value, &mut buffer).unwrap();
+        buffer
+    };
+    assert_eq!(vlq(0), vec![0x00]);
+    assert_eq!(vlq(0x40), vec![0x40]);
+    assert_eq!(vlq(0x7F), vec![0x7F]);
+    assert_eq!(vlq(0x80), vec![0x81, 0x00]);
+    assert_eq!(vlq(0x2000), vec![0xC0, 0x00]);
+    assert_eq!(vlq(0x3FFF), vec![0xFF, 0x7F]);
+    assert_eq!(vlq(0x4000), vec![0x81, 0x80, 0x00]);
+    assert_eq!(vlq(0x0FFF_FFFF), vec![0xFF, 0xFF, 0xFF, 0x7F]);
+    assert!(write_vlq(0x1000_0000, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_morse_smf() {
+    use crate::morse_alphabet::{A, E, SPACE};
+    use crate::polyphonia::notable_notes;
+    use crate::timing::MorseTiming;
+
+    assert_eq!(notable_notes::A4.midi_number(), 69);
+    assert_eq!(notable_notes::C4.midi_number(), 60);
+
+    let timing = MorseTiming::from_wpm(12.0);
+    let settings = MidiSettings {
+        bpm: MidiSettings::bpm_of_dot(timing.dot),
+        note: 69,
+        velocity: 100,
+    };
+    assert!((settings.bpm - 150.0).abs() < 1e-3);
+    let events = morse_events(&timing.keying([A, SPACE, E]), timing.dot, &settings).unwrap();
+    let on = MidiEvent::NoteOn {
+        note: 69,
+        velocity: 100,
+    };
+    let off = MidiEvent::NoteOff { note: 69 };
+    assert_eq!(
+        events,
+        vec![
+            (0, MidiEvent::Tempo(400_000)),
+            (0, on),
+            (120, off),
+            (120, on),
+            (360, off),
+            (840, on),
+            (120, off),
+            (0, MidiEvent::EndOfTrack),
+        ]
+    );
+
+    let mut bytes: Vec<u8> = Vec::new();
+    write_smf(&events, &mut bytes).unwrap();
+    assert_eq!(&bytes[..14], b"MThd\0\0\0\x06\0\0\0\x01\x01\xE0");
+    assert_eq!(&bytes[14..18], b"MTrk");
+    let track = &bytes[22..];
+    assert_eq!(
+        u32::from_be_bytes([bytes[18], bytes[19], bytes[20], bytes[21]]) as usize,
+        track.len()
+    );
+    assert_eq!(&track[..7], &[0x00, 0xFF, 0x51, 0x03, 0x06, 0x1A, 0x80]);
+    // the dah lasts 360 ticks, written in two bytes
+    assert_eq!(&track[19..23], &[0x82, 0x68, 0x80, 0x45]);
+    assert_eq!(&track[track.len() - 4..], &[0x00, 0xFF, 0x2F, 0x00]);
+
+    // tempos slower than 24 bits of microseconds can't be written
+    assert!(write_smf(&[(0, MidiEvent::Tempo(MAX_TEMPO_MICROS))], &mut Vec::new()).is_ok());
+    assert!(write_smf(&[(0, MidiEvent::Tempo(20_000_000))], &mut Vec::new()).is_err());
+
+    // delta times are checked before they are narrowed, a wrapped one would look valid
+    let delayed = |ticks: u64| {
+        let keying = [
+            KeyingElement {
+                tone: false,
+                duration: ticks as f64 / TICKS_PER_DOT as f64,
+            },
+            KeyingElement {
+                tone: true,
+                duration: 1.0,
+            },
+        ];
+        morse_events(&keying, 1.0, &settings)
+    };
+    assert_eq!(delayed(MAX_VLQ as u64).unwrap()[1].0, MAX_VLQ);
+    assert!(delayed(MAX_VLQ as u64 + 1).is_err());
+    assert!(delayed((1 << 32) + 10).is_err());
+}
@@ -2,11 +2,20 @@ use std::str::{self, FromStr};
 
 use clap::{self, Parser};
 
+use crate::midi::MAX_TEMPO_MICROS;
+use crate::polyphonia::{Amplitude, EnvelopeShape, Note, Waveform, SAMPLE_RATE};
+use crate::subtitles::{Granularity, SubtitleFormat};
+use crate::timing::DEFAULT_WPM;
+use crate::utils::format_from_extension;
+use crate::wav::{RawFormat, SampleFormat};
+
 /// tuple struct with two string slices with static lifetime (aka: as long as the program runs)
 #[derive(Debug, Clone)]
 pub enum MorseTraductionType {
     Text,
     Audio,
+    Midi,
+    Timing,
 }
 
 impl FromStr for MorseTraductionType {
@@ -15,15 +24,71 @@ impl FromStr for MorseTraductionType {
         match s.to_ascii_lowercase().as_str() {
             "text" => Ok(MorseTraductionType::Text),
             "audio" => Ok(MorseTraductionType::Audio),
+            "midi" => Ok(MorseTraductionType::Midi),
+            "timing" => Ok(MorseTraductionType::Timing),
             _ => Err(format!("Type of output not found: {}", s)),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum DecodedFormat {
+    Text,
+    JsonLines,
+}
+
+impl FromStr for DecodedFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(DecodedFormat::Text),
+            "jsonl" | "json-lines" => Ok(DecodedFormat::JsonLines),
+            _ => Err(format!("Decoded format not found: {}", s)),
+        }
+    }
+}
+
+/// Container of the audio output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioFormat {
+    Wav,
+    Aiff,
+    Au,
+    Flac,
+    Raw(RawFormat),
+}
+
+impl FromStr for AudioFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "wav" => Ok(AudioFormat::Wav),
+            "aiff" | "aif" | "aifc" => Ok(AudioFormat::Aiff),
+            "au" | "snd" => Ok(AudioFormat::Au),
+            "flac" => Ok(AudioFormat::Flac),
+            raw => RawFormat::from_str(raw)
+                .map(AudioFormat::Raw)
+                .map_err(|_| format!("Audio format not found: {}", s)),
+        }
+    }
+}
+
+impl AudioFormat {
+    /// Container given by the extension of the file, WAV when it isn't known.
+    /// Raw PCM has no extension of its own, it's chosen only explicitly.
+    pub fn from_file_name(file_name: &str) -> Self {
+        match format_from_extension(file_name) {
+            Some(AudioFormat::Raw(_)) | None => AudioFormat::Wav,
+            Some(format) => format,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum MorseCommand {
     Encode,
     Decode,
+    Check,
 }
 
 impl FromStr for MorseCommand {
@@ -32,22 +97,85 @@ impl FromStr for MorseCommand {
         match s.to_ascii_lowercase().as_str() {
             "e" | "encode" => Ok(MorseCommand::Encode),
             "d" | "decode" => Ok(MorseCommand::Decode),
+            "c" | "check" => Ok(MorseCommand::Check),
             _ => Err(format!("Morse command not found: {}", s)),
         }
     }
 }
 
+fn parse_wpm(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(wpm) if wpm.is_finite() && wpm > 0.0 => Ok(wpm),
+        _ => Err(format!(
+            "Speed must be a positive number of words per minute: {}",
+            s
+        )),
+    }
+}
+
+fn parse_rise_time(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(rise_time) if rise_time.is_finite() && rise_time >= 0.0 => Ok(rise_time),
+        _ => Err(format!(
+            "Rise time must be a number of milliseconds from 0: {}",
+            s
+        )),
+    }
+}
+
+fn parse_tempo(s: &str) -> Result<f32, String> {
+    // a quarter note lasts at most 24 bits of microseconds
+    let min_tempo = 60_000_000.0 / MAX_TEMPO_MICROS as f32;
+    match s.parse::<f32>() {
+        Ok(tempo)
+            if tempo.is_finite()
+                && tempo > 0.0
+                && (60_000_000.0 / tempo).round() <= MAX_TEMPO_MICROS as f32 =>
+        {
+            Ok(tempo)
+        }
+        _ => Err(format!(
+            "Tempo must be a number of beats per minute from {:.2}: {}",
+            min_tempo, s
+        )),
+    }
+}
+
+fn parse_sample_rate(s: &str) -> Result<u32, String> {
+    match s.trim_end_matches("Hz").parse::<u32>() {
+        Ok(sample_rate) if sample_rate > 0 => Ok(sample_rate),
+        _ => Err(format!(
+            "Sample rate must be a positive number of Hz: {}",
+            s
+        )),
+    }
+}
+
+fn parse_candidates(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(candidates) if candidates > 0 => Ok(candidates),
+        _ => Err(format!(
+            "Number of readings must be a positive integer: {}",
+            s
+        )),
+    }
+}
+
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 pub struct MorseArgs {
     /// Morse command:
     /// -encode
     /// -decode
+    /// -check (validate morse without translating it, failing if errors are found)
     pub morse_command: MorseCommand,
 
     /// Type of traduction from human readable text to morse:
     /// -text
     /// -audio
+    /// -midi (standard MIDI file with a note for every dot and dash, at the pitch of --tone)
+    /// -timing (decode only, key log of "down_ms,up_ms" lines or alternating mark/space durations)
+    #[clap(default_value = "text")]
     pub traduction_type: MorseTraductionType,
 
     /// Name of the file to read, if the value is "-" read from stdin
@@ -57,4 +185,145 @@ pub struct MorseArgs {
     /// Name of the file to read, if the value is "-" write to stdout
     #[clap(short, long, default_value = "-")]
     pub out_file: String,
+
+    /// Speed of the audio in words per minute, with PARIS timing
+    #[clap(long, default_value_t = DEFAULT_WPM, value_parser = parse_wpm)]
+    pub wpm: f32,
+
+    /// Effective speed in words per minute for Farnsworth timing: letters are sent at
+    /// --wpm and the gaps between letters and words are stretched following the ARRL standard
+    #[clap(long, value_name = "WPM", value_parser = parse_wpm)]
+    pub farnsworth: Option<f32>,
+
+    /// Frequency of the audio tone in Hz or name of a note, e.g. 600, C5, C#5
+    #[clap(long, default_value = "A4")]
+    pub tone: Note,
+
+    /// Volume of the audio: silent, low, medium, high, a scaling between 0.01 and 1 or dBFS down to -40 (e.g. -6dB)
+    #[clap(long, default_value = "medium", allow_hyphen_values = true)]
+    pub volume: Amplitude,
+
+    /// Rise and fall time in milliseconds of the keyed tone, 0 keys it abruptly
+    /// (square, sawtooth and noise waves keep a 1 ms rise)
+    #[clap(long, default_value_t = 5.0, value_parser = parse_rise_time)]
+    pub rise_time: f32,
+
+    /// Shape of the rise and fall of the keyed tone:
+    /// -raised-cosine
+    /// -blackman
+    #[clap(long, default_value = "raised-cosine")]
+    pub envelope: EnvelopeShape,
+
+    /// Waveform of the tone: sine, square, triangle, sawtooth, noise
+    /// or the band-limited square-bl, triangle-bl, sawtooth-bl
+    #[clap(long, default_value = "sine")]
+    pub waveform: Waveform,
+
+    /// Format of the audio, by default given by the extension of the output file:
+    /// -wav
+    /// -aiff (aiff-c for f32 samples)
+    /// -au
+    /// -flac (lossless compression, u8, s16 or s24 samples)
+    /// -s16le, s16be, u8, f32le (headerless raw PCM, the format is reported on stderr)
+    #[clap(long)]
+    pub format: Option<AudioFormat>,
+
+    /// Sample rate of the audio in Hz, e.g. 8000, 16000, 44100, 48000
+    #[clap(long, default_value_t = SAMPLE_RATE, value_parser = parse_sample_rate)]
+    pub sample_rate: u32,
+
+    /// Encoding of the samples of wav, aiff and au audio: u8, s16, s24, s32 or f32
+    #[clap(long, default_value = "s16")]
+    pub sample_format: SampleFormat,
+
+    /// Title written in the metadata of wav audio, the first line of the message if not given
+    #[clap(long)]
+    pub title: Option<String>,
+
+    /// Tempo of midi output in beats per minute, a dot lasts a sixteenth note;
+    /// by default the rhythm plays at the speed of --wpm
+    #[clap(long, value_name = "BPM", value_parser = parse_tempo)]
+    pub tempo: Option<f32>,
+
+    /// Velocity of the notes of midi output, between 1 and 127
+    #[clap(long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(1..=127))]
+    pub velocity: u8,
+
+    /// Name of the file where subtitles of the audio are written, synchronized with it
+    #[clap(long)]
+    pub subtitles: Option<String>,
+
+    /// Format of the subtitles: srt or vtt, by default given by the extension of the file
+    #[clap(long)]
+    pub subtitle_format: Option<SubtitleFormat>,
+
+    /// Text shown by every subtitle: char, word or line
+    #[clap(long, default_value = "word")]
+    pub subtitle_granularity: Granularity,
+
+    /// Format of the text decoded from timings:
+    /// -text
+    /// -jsonl (one letter per line with start/end time and confidence)
+    #[clap(long, default_value = "text")]
+    pub decoded_format: DecodedFormat,
+
+    /// Marker written after decoded letters with a confidence lower than the threshold
+    #[clap(long, default_value = "?")]
+    pub confidence_marker: String,
+
+    /// Confidence, between 0 and 1, under which a decoded letter is marked
+    #[clap(long, default_value_t = 0.5)]
+    pub confidence_threshold: f32,
+
+    /// Decode morse written without letter separators, listing the N most likely readings
+    #[clap(long, value_name = "N", value_parser = parse_candidates)]
+    pub segment: Option<usize>,
+
+    /// Decode correcting mistyped codes, writing the raw and the corrected reading of
+    /// every line, then the nearest letters of every unknown code, separated by tabs
+    #[clap(long)]
+    pub correct: bool,
+
+    /// Word list, one word per line, used to rank the readings of ambiguous morse
+    #[clap(long)]
+    pub dictionary: Option<String>,
+}
+
+#[test]
+fn test_audio_format() {
+    assert_eq!(AudioFormat::from_file_name("morse.aiff"), AudioFormat::Aiff);
+    assert_eq!(
+        AudioFormat::from_file_name("out/morse.AIFC"),
+        AudioFormat::Aiff
+    );
+    assert_eq!(AudioFormat::from_file_name("morse.snd"), AudioFormat::Au);
+    assert_eq!(AudioFormat::from_file_name("morse.flac"), AudioFormat::Flac);
+    // raw PCM, unknown extensions and stdout fall back to WAV
+    assert_eq!(AudioFormat::from_file_name("morse.s16le"), AudioFormat::Wav);
+    assert_eq!(AudioFormat::from_file_name("morse.mp3"), AudioFormat::Wav);
+    assert_eq!(AudioFormat::from_file_name("-"), AudioFormat::Wav);
+}
+
+#[test]
+fn test_segment_argument() {
+    let segment = |value: &str| {
+        MorseArgs::try_parse_from(["morse_traducer", "decode", "-i", "-", "--segment", value])
+            .map(|args| args.segment)
+    };
+    assert_eq!(segment("3").unwrap(), Some(3));
+    assert!(segment("0").is_err());
+    assert!(segment("-1").is_err());
+}
+
+#[test]
+fn test_rise_time_argument() {
+    let rise_time = |value: &str| {
+        MorseArgs::try_parse_from(["morse_traducer", "encode", "-i", "-", "--rise-time", value])
+            .map(|args| args.rise_time)
+    };
+    assert_eq!(rise_time("0").unwrap(), 0.0);
+    assert_eq!(rise_time("2.5").unwrap(), 2.5);
+    assert!(rise_time("-5").is_err());
+    assert!(rise_time("NaN").is_err());
+    assert!(rise_time("inf").is_err());
 }
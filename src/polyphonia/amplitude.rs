@@ -1,3 +1,6 @@
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Amplitude {
     Silent,
     Low,
@@ -6,7 +9,53 @@ pub enum Amplitude {
     Custom(f32),
 }
 
+impl FromStr for Amplitude {
+    type Err = String;
+    /// Parse a level (`silent`, `low`, `medium`, `high`), a scaling between 0.01 and 1
+    /// or a level in dBFS between -40 and 0 (`-6dB`, `-6dBFS`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.trim().to_ascii_lowercase();
+        match lowercase.as_str() {
+            "silent" => Ok(Amplitude::Silent),
+            "low" => Ok(Amplitude::Low),
+            "medium" => Ok(Amplitude::Medium),
+            "high" => Ok(Amplitude::High),
+            x if x.ends_with("dbfs") || x.ends_with("db") => {
+                match x
+                    .trim_end_matches("fs")
+                    .trim_end_matches("db")
+                    .trim()
+                    .parse::<f32>()
+                {
+                    Ok(dbfs) if (-40.0..=0.0).contains(&dbfs) => Ok(Amplitude::from_dbfs(dbfs)),
+                    _ => Err(format!(
+                        "Volume in dBFS must be between -40 and 0, or silent: {}",
+                        s
+                    )),
+                }
+            }
+            x => match x.parse::<f32>() {
+                Ok(volume) if (0.01..=1.0).contains(&volume) => Ok(Amplitude::Custom(volume)),
+                Ok(_) => Err(format!(
+                    "Volume must be between 0.01 and 1, or silent: {}",
+                    s
+                )),
+                _ => Err(format!("Volume not found: {}", s)),
+            },
+        }
+    }
+}
+
 impl Amplitude {
+    /// Volume relative to the full scale in decibels, 0 dBFS is the loudest.
+    pub fn from_dbfs(dbfs: f32) -> Self {
+        Amplitude::Custom(f32::powf(10.0, dbfs / 20.0))
+    }
+
+    pub fn to_dbfs(&self) -> f32 {
+        20.0 * f32::log10(self.scaling())
+    }
+
     pub fn scaling(&self) -> f32 {
         match self {
             Amplitude::Silent => 0.0,
@@ -17,3 +66,19 @@ impl Amplitude {
         }
     }
 }
+
+#[test]
+fn test_amplitude_from_str() {
+    assert_eq!(Amplitude::from_str("High"), Ok(Amplitude::High));
+    assert_eq!(Amplitude::from_str("0.25"), Ok(Amplitude::Custom(0.25)));
+    assert!((Amplitude::from_str("-6dB").unwrap().scaling() - 0.501).abs() < 1e-3);
+    assert!((Amplitude::from_str("-20 dBFS").unwrap().to_dbfs() + 20.0).abs() < 1e-3);
+    assert!(Amplitude::from_str("3dB").is_err());
+    assert!(Amplitude::from_str("loud").is_err());
+    // levels under the quietest scaling would be silently raised to it
+    assert_eq!(Amplitude::from_str("0.01"), Ok(Amplitude::Custom(0.01)));
+    assert!(Amplitude::from_str("0").is_err());
+    assert!(Amplitude::from_str("0.005").is_err());
+    assert!(Amplitude::from_str("-60dB").is_err());
+    assert!((Amplitude::from_str("-40dB").unwrap().scaling() - 0.01).abs() < 1e-6);
+}
@@ -0,0 +1,113 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+/// Shape of the attack and release of a keyed tone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnvelopeShape {
+    RaisedCosine,
+    Blackman,
+}
+
+impl FromStr for EnvelopeShape {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cosine" | "raised-cosine" => Ok(EnvelopeShape::RaisedCosine),
+            "blackman" => Ok(EnvelopeShape::Blackman),
+            _ => Err(format!("Envelope shape not found: {}", s)),
+        }
+    }
+}
+
+impl EnvelopeShape {
+    /// Gain rising from 0 to 1 while `x` goes from 0 to 1.
+    pub fn ramp(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            EnvelopeShape::RaisedCosine => 0.5 - 0.5 * f32::cos(PI * x),
+            EnvelopeShape::Blackman => {
+                0.42 - 0.5 * f32::cos(PI * x) + 0.08 * f32::cos(2.0 * PI * x)
+            }
+        }
+    }
+}
+
+/// Amplitude envelope of a keyed tone: it rises from silence in `rise` seconds,
+/// holds the full amplitude and falls back to silence in the same time.
+///
+/// Starting and stopping a tone abruptly makes audible clicks, shaping the edges
+/// keeps the spectrum of the keyed tone narrow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Envelope {
+    pub shape: EnvelopeShape,
+    pub rise: f32,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Envelope {
+            shape: EnvelopeShape::RaisedCosine,
+            rise: 0.005,
+        }
+    }
+}
+
+impl Envelope {
+    /// Gain at `time` seconds from the start of a tone lasting `duration` seconds.
+    pub fn gain(&self, time: f32, duration: f32) -> f32 {
+        // short tones rise and fall without holding
+        let rise = self.rise.min(duration / 2.0);
+        if rise <= 0.0 {
+            return 1.0;
+        }
+        match time {
+            t if t < rise => self.shape.ramp(t / rise),
+            t if t > duration - rise => self.shape.ramp((duration - t) / rise),
+            _ => 1.0,
+        }
+    }
+}
+
+#[test]
+fn test_keying_spectrum() {
+    use super::{notable_notes, Amplitude, SAMPLE_RATE};
+
+    // power of the samples at a frequency, with the Goertzel algorithm
+    fn power(samples: &[f32], frequency: f32) -> f32 {
+        let coefficient = 2.0 * f32::cos(2.0 * PI * frequency / SAMPLE_RATE as f32);
+        let (mut previous, mut before_previous) = (0_f32, 0_f32);
+        for sample in samples {
+            let current = *sample + coefficient * previous - before_previous;
+            before_previous = previous;
+            previous = current;
+        }
+        previous * previous + before_previous * before_previous
+            - coefficient * previous * before_previous
+    }
+    // energy of the splatter, far away from the tone
+    fn splatter(samples: &[f32]) -> f32 {
+        (0..40)
+            .map(|i| power(samples, 1500.0 + 100.0 * i as f32))
+            .sum()
+    }
+
+    let hard = Envelope {
+        rise: 0.0,
+        ..Default::default()
+    };
+    let tone = notable_notes::C5;
+    let keyed_hard = tone.keyed_wave(0.06, &Amplitude::Medium, &hard, SAMPLE_RATE);
+    for shape in [EnvelopeShape::RaisedCosine, EnvelopeShape::Blackman] {
+        let soft = Envelope { shape, rise: 0.005 };
+        let keyed_soft = tone.keyed_wave(0.06, &Amplitude::Medium, &soft, SAMPLE_RATE);
+        let attenuation = 10.0 * f32::log10(splatter(&keyed_hard) / splatter(&keyed_soft));
+        assert!(attenuation > 20.0, "{:?}: {} dB", shape, attenuation);
+        // the tone itself keeps most of its power
+        assert!(
+            power(&keyed_soft, tone.get_frequency())
+                > 0.8 * power(&keyed_hard, tone.get_frequency())
+        );
+    }
+    assert_eq!(Envelope::default().gain(0.0, 0.06), 0.0);
+    assert_eq!(Envelope::default().gain(0.03, 0.06), 1.0);
+}
@@ -1,14 +1,21 @@
 //public modules
 pub mod amplitude;
+pub mod envelope;
 pub mod notable_notes;
 pub mod note;
+pub mod oscillator;
+pub mod waveform;
 
 // public uses
 pub use amplitude::Amplitude;
+pub use envelope::{Envelope, EnvelopeShape};
 pub use note::Note;
+pub use oscillator::Oscillator;
+pub use waveform::Waveform;
 
 use std::ops::Fn;
 
+/// Sample rate used when no other is given.
 pub const SAMPLE_RATE: u32 = 44100;
 pub const MAX_AMPLITUDE: f32 = i16::MAX as f32;
 
@@ -20,19 +27,26 @@ fn get_w(frequency: f32, time: f32, sample_rate: f32) -> f32 {
     2.0 * std::f32::consts::PI * frequency * time / sample_rate
 }
 
-fn natural_oscillator<F>(
+/// Oscillator whose amplitude and frequency change over time, times are in samples.
+fn natural_oscillator<F, M>(
     frequency: f32,
+    sample_rate: u32,
     time_start: f32,
     time_curr: f32,
     amplitude_calculator: F,
-    amplitude_modulator: F,
+    frequency_modulator: M,
 ) -> f32
 where
     F: Fn(f32) -> f32,
+    M: Fn(f32) -> f32,
 {
     let time_delta = time_curr - time_start;
     oscillator(
-        frequency * amplitude_modulator(time_delta),
+        get_w(
+            frequency * frequency_modulator(time_delta),
+            time_delta,
+            sample_rate as f32,
+        ),
         amplitude_calculator(time_delta),
     )
 }
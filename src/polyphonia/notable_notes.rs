@@ -153,3 +153,192 @@ pub const A8: Note = Note(7040.00_f32);
 pub const A8_SH: Note = Note(7458.62_f32);
 pub const B8_B: Note = Note(7458.62_f32);
 pub const B8: Note = Note(7902.13_f32);
+
+/// Every notable note with the name of its constant.
+pub const NOTES: [(&str, Note); 153] = [
+    ("C0", C0),
+    ("C0_SH", C0_SH),
+    ("D0_B", D0_B),
+    ("D0", D0),
+    ("D0_SH", D0_SH),
+    ("E0_B", E0_B),
+    ("E0", E0),
+    ("F0", F0),
+    ("F0_SH", F0_SH),
+    ("G0_B", G0_B),
+    ("G0", G0),
+    ("G0_SH", G0_SH),
+    ("A0_B", A0_B),
+    ("A0", A0),
+    ("A0_SH", A0_SH),
+    ("B0_B", B0_B),
+    ("B0", B0),
+    ("C1", C1),
+    ("C1_SH", C1_SH),
+    ("D1_B", D1_B),
+    ("D1", D1),
+    ("D1_SH", D1_SH),
+    ("E1_B", E1_B),
+    ("E1", E1),
+    ("F1", F1),
+    ("F1_SH", F1_SH),
+    ("G1_B", G1_B),
+    ("G1", G1),
+    ("G1_SH", G1_SH),
+    ("A1_B", A1_B),
+    ("A1", A1),
+    ("A1_SH", A1_SH),
+    ("B1_B", B1_B),
+    ("B1", B1),
+    ("C2", C2),
+    ("C2_SH", C2_SH),
+    ("D2_B", D2_B),
+    ("D2", D2),
+    ("D2_SH", D2_SH),
+    ("E2_B", E2_B),
+    ("E2", E2),
+    ("F2", F2),
+    ("F2_SH", F2_SH),
+    ("G2_B", G2_B),
+    ("G2", G2),
+    ("G2_SH", G2_SH),
+    ("A2_B", A2_B),
+    ("A2", A2),
+    ("A2_SH", A2_SH),
+    ("B2_B", B2_B),
+    ("B2", B2),
+    ("C3", C3),
+    ("C3_SH", C3_SH),
+    ("D3_B", D3_B),
+    ("D3", D3),
+    ("D3_SH", D3_SH),
+    ("E3_B", E3_B),
+    ("E3", E3),
+    ("F3", F3),
+    ("F3_SH", F3_SH),
+    ("G3_B", G3_B),
+    ("G3", G3),
+    ("G3_SH", G3_SH),
+    ("A3_B", A3_B),
+    ("A3", A3),
+    ("A3_SH", A3_SH),
+    ("B3_B", B3_B),
+    ("B3", B3),
+    ("C4", C4),
+    ("C4_SH", C4_SH),
+    ("D4_B", D4_B),
+    ("D4", D4),
+    ("D4_SH", D4_SH),
+    ("E4_B", E4_B),
+    ("E4", E4),
+    ("F4", F4),
+    ("F4_SH", F4_SH),
+    ("G4_B", G4_B),
+    ("G4", G4),
+    ("G4_SH", G4_SH),
+    ("A4_B", A4_B),
+    ("A4", A4),
+    ("A4_SH", A4_SH),
+    ("B4_B", B4_B),
+    ("B4", B4),
+    ("C5", C5),
+    ("C5_SH", C5_SH),
+    ("D5_B", D5_B),
+    ("D5", D5),
+    ("D5_SH", D5_SH),
+    ("E5_B", E5_B),
+    ("E5", E5),
+    ("F5", F5),
+    ("F5_SH", F5_SH),
+    ("G5_B", G5_B),
+    ("G5", G5),
+    ("G5_SH", G5_SH),
+    ("A5_B", A5_B),
+    ("A5", A5),
+    ("A5_SH", A5_SH),
+    ("B5_B", B5_B),
+    ("B5", B5),
+    ("C6", C6),
+    ("C6_SH", C6_SH),
+    ("D6_B", D6_B),
+    ("D6", D6),
+    ("D6_SH", D6_SH),
+    ("E6_B", E6_B),
+    ("E6", E6),
+    ("F6", F6),
+    ("F6_SH", F6_SH),
+    ("G6_B", G6_B),
+    ("G6", G6),
+    ("G6_SH", G6_SH),
+    ("A6_B", A6_B),
+    ("A6", A6),
+    ("A6_SH", A6_SH),
+    ("B6_B", B6_B),
+    ("B6", B6),
+    ("C7", C7),
+    ("C7_SH", C7_SH),
+    ("D7_B", D7_B),
+    ("D7", D7),
+    ("D7_SH", D7_SH),
+    ("E7_B", E7_B),
+    ("E7", E7),
+    ("F7", F7),
+    ("F7_SH", F7_SH),
+    ("G7_B", G7_B),
+    ("G7", G7),
+    ("G7_SH", G7_SH),
+    ("A7_B", A7_B),
+    ("A7", A7),
+    ("A7_SH", A7_SH),
+    ("B7_B", B7_B),
+    ("B7", B7),
+    ("C8", C8),
+    ("C8_SH", C8_SH),
+    ("D8_B", D8_B),
+    ("D8", D8),
+    ("D8_SH", D8_SH),
+    ("E8_B", E8_B),
+    ("E8", E8),
+    ("F8", F8),
+    ("F8_SH", F8_SH),
+    ("G8_B", G8_B),
+    ("G8", G8),
+    ("G8_SH", G8_SH),
+    ("A8_B", A8_B),
+    ("A8", A8),
+    ("A8_SH", A8_SH),
+    ("B8_B", B8_B),
+    ("B8", B8),
+];
+
+/// Find a notable note from its name, written as the constant (`C5_SH`, `D5_B`)
+/// or with the usual accidentals (`C#5`, `Db5`).
+pub fn from_name(name: &str) -> Option<Note> {
+    let mut chars = name.trim().chars();
+    let pitch = chars.next()?.to_ascii_uppercase();
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next()? {
+        '#' => ("_SH", &rest[1..]),
+        'b' => ("_B", &rest[1..]),
+        _ => match rest.to_ascii_uppercase() {
+            x if x.ends_with("_SH") => ("_SH", &rest[..rest.len() - 3]),
+            x if x.ends_with("_B") => ("_B", &rest[..rest.len() - 2]),
+            _ => ("", rest),
+        },
+    };
+    let constant = format!("{}{}{}", pitch, octave, accidental);
+    NOTES
+        .iter()
+        .find(|(name, _)| *name == constant)
+        .map(|(_, note)| *note)
+}
+
+#[test]
+fn test_from_name() {
+    assert_eq!(from_name("A4"), Some(A4));
+    assert_eq!(from_name("c#5"), Some(C5_SH));
+    assert_eq!(from_name("Db5"), Some(D5_B));
+    assert_eq!(from_name("G3_SH"), Some(G3_SH));
+    assert_eq!(from_name("H4"), None);
+    assert_eq!(from_name("C9"), None);
+}
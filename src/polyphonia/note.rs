@@ -1,45 +1,118 @@
-use super::{get_w, oscillator, Amplitude, MAX_AMPLITUDE, SAMPLE_RATE};
+use super::{
+    get_w, natural_oscillator, notable_notes, oscillator, Amplitude, Envelope, Oscillator,
+    Waveform, SAMPLE_RATE,
+};
+use std::str::FromStr;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Note(pub f32);
 
+impl FromStr for Note {
+    type Err = String;
+    /// Parse a frequency in Hz (`600`, `600Hz`) or the name of a notable note (`C5`, `C#5`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.trim().to_ascii_lowercase();
+        match lowercase.trim_end_matches("hz").trim().parse::<f32>() {
+            Ok(frequency) if frequency > 0.0 && frequency < SAMPLE_RATE as f32 / 2.0 => {
+                Ok(Note(frequency))
+            }
+            Ok(_) => Err(format!("Frequency out of the audible range: {}", s)),
+            Err(_) => notable_notes::from_name(s).ok_or(format!("Note not found: {}", s)),
+        }
+    }
+}
+
 impl Note {
     pub fn get_frequency(&self) -> f32 {
         self.0
     }
 
-    pub fn combine(notes: &[Self], secs: f32, volume: &Amplitude) -> Vec<i16> {
-        let nsamples = secs * SAMPLE_RATE as f32;
+    /// Nearest MIDI note number, A4 at 440 Hz is 69 and every semitone counts one.
+    pub fn midi_number(&self) -> u8 {
+        (69.0 + 12.0 * (self.get_frequency() / 440.0).log2())
+            .round()
+            .clamp(0.0, 127.0) as u8
+    }
+
+    /// Oscillator at the frequency of the note, starting from phase 0 at the default sample rate.
+    pub fn oscillator(&self) -> Oscillator {
+        Oscillator::new(self.get_frequency())
+    }
+
+    pub fn combine(notes: &[Self], secs: f32, volume: &Amplitude, sample_rate: u32) -> Vec<f32> {
+        let nsamples = secs * sample_rate as f32;
+        let mut oscillators = notes
+            .iter()
+            .map(|note| note.oscillator().with_sample_rate(sample_rate))
+            .collect::<Vec<_>>();
         (0..nsamples as u32)
-            .map(|t| {
-                notes.iter().map(move |note| match volume {
-                    Amplitude::Silent => 0_f32,
-                    _ => oscillator(
-                        get_w(note.get_frequency(), t as f32, SAMPLE_RATE as f32),
-                        1_f32,
-                    ),
-                })
-            })
-            .map(|step| {
+            .map(|_| {
                 // TODO: refactoring implementing Mean trait for iter
                 // https://stackoverflow.com/questions/43921436/extend-iterator-with-a-mean-method#answer-43926007
                 // You can use as base rust impl for Sum and their proc macro
                 // https://github.com/rust-lang/rust/blob/master/library/core/src/iter/traits/accum.rs
-                let (len, sum) = step.fold((0, 0_f32), |(len, sum), x| (len + 1, sum + x));
-                f32::floor(MAX_AMPLITUDE * volume.scaling() * sum / len as f32) as i16
+                let (len, sum) = oscillators
+                    .iter_mut()
+                    .map(|oscillator| match volume {
+                        Amplitude::Silent => 0_f32,
+                        _ => oscillator.next_sample(),
+                    })
+                    .fold((0, 0_f32), |(len, sum), x| (len + 1, sum + x));
+                volume.scaling() * sum / len as f32
             })
-            .collect::<Vec<i16>>()
+            .collect::<Vec<f32>>()
     }
 
-    pub fn audio_wave(&self, secs: f32, volume: &Amplitude) -> Vec<i16> {
-        let nsamples = secs * SAMPLE_RATE as f32;
+    pub fn audio_wave(&self, secs: f32, volume: &Amplitude, sample_rate: u32) -> Vec<f32> {
+        let nsamples = secs * sample_rate as f32;
         (0..nsamples as u32)
             .map(|t| match *volume {
-                Amplitude::Silent => 0_i16,
-                _ => f32::floor(oscillator(
-                    get_w(self.get_frequency(), t as f32, SAMPLE_RATE as f32),
-                    MAX_AMPLITUDE * volume.scaling(),
-                )) as i16,
+                Amplitude::Silent => 0_f32,
+                _ => oscillator(
+                    get_w(self.get_frequency(), t as f32, sample_rate as f32),
+                    volume.scaling(),
+                ),
             })
             .collect()
     }
+
+    /// Tone shaped by the envelope, to key it without clicks.
+    pub fn keyed_wave(
+        &self,
+        secs: f32,
+        volume: &Amplitude,
+        envelope: &Envelope,
+        sample_rate: u32,
+    ) -> Vec<f32> {
+        let nsamples = secs * sample_rate as f32;
+        let amplitude = volume.scaling();
+        (0..nsamples as u32)
+            .map(|t| match *volume {
+                Amplitude::Silent => 0_f32,
+                _ => natural_oscillator(
+                    self.get_frequency(),
+                    sample_rate,
+                    0.0,
+                    t as f32,
+                    |time| amplitude * envelope.gain(time / sample_rate as f32, secs),
+                    |_| 1.0,
+                ),
+            })
+            .collect()
+    }
+
+    /// Tone of the note with the given waveform.
+    pub fn synthesize(
+        &self,
+        secs: f32,
+        volume: &Amplitude,
+        waveform: Waveform,
+        sample_rate: u32,
+    ) -> Vec<f32> {
+        let nsamples = secs * sample_rate as f32;
+        self.oscillator()
+            .with_waveform(waveform)
+            .with_sample_rate(sample_rate)
+            .render(nsamples as u32, |_| volume.scaling())
+    }
 }
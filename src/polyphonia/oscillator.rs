@@ -0,0 +1,140 @@
+use super::{Waveform, SAMPLE_RATE};
+use std::f64::consts::PI;
+
+/// Oscillator that keeps its phase between calls, so that consecutive chunks
+/// of the same tone join without discontinuities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Oscillator {
+    frequency: f32,
+    sample_rate: u32,
+    waveform: Waveform,
+    // radians, always in [0, 2π)
+    phase: f64,
+    // xorshift state of the noise
+    noise: u32,
+}
+
+impl Oscillator {
+    /// Sine oscillator at the default sample rate.
+    pub fn new(frequency: f32) -> Self {
+        Oscillator {
+            frequency,
+            sample_rate: SAMPLE_RATE,
+            waveform: Waveform::Sine,
+            phase: 0.0,
+            noise: 0x9E37_79B9,
+        }
+    }
+
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// Change the frequency, the phase carries on from where it is.
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+
+    pub fn phase(&self) -> f64 {
+        self.phase
+    }
+
+    fn step(&self) -> f64 {
+        2.0 * PI * self.frequency as f64 / self.sample_rate as f64
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let sample = match self.waveform {
+            Waveform::Noise => {
+                self.noise ^= self.noise << 13;
+                self.noise ^= self.noise >> 17;
+                self.noise ^= self.noise << 5;
+                self.noise as f64 / u32::MAX as f64 * 2.0 - 1.0
+            }
+            waveform => waveform.sample(self.phase, self.step() / (2.0 * PI)),
+        };
+        self.phase = (self.phase + self.step()) % (2.0 * PI);
+        sample as f32
+    }
+
+    /// Move the phase forward by `nsamples` samples without rendering them.
+    pub fn skip(&mut self, nsamples: u32) {
+        self.phase = (self.phase + nsamples as f64 * self.step()) % (2.0 * PI);
+    }
+
+    /// Render `nsamples` samples, scaling each one by `amplitude` of its index in the chunk.
+    pub fn render<F>(&mut self, nsamples: u32, amplitude: F) -> Vec<f32>
+    where
+        F: Fn(u32) -> f32,
+    {
+        (0..nsamples)
+            .map(|t| amplitude(t) * self.next_sample())
+            .collect()
+    }
+
+    /// Samples to render so that the wave crosses zero right after the last of them,
+    /// 0 if the next sample is already a zero.
+    pub fn samples_to_zero_crossing(&self) -> u32 {
+        let remaining = match self.phase % PI {
+            x if x < 1e-9 => return 0,
+            x => PI - x,
+        };
+        f64::ceil(remaining / self.step()) as u32
+    }
+
+    /// Samples rendered since the wave last crossed zero, dropping them ends the chunk
+    /// right before the crossing.
+    pub fn samples_since_zero_crossing(&self) -> u32 {
+        f64::floor((self.phase % PI) / self.step()) as u32
+    }
+}
+
+#[test]
+fn test_phase_continuity() {
+    let mut whole = Oscillator::new(600.0);
+    let mut chunked = whole.clone();
+    let expected = whole.render(300, |_| 1.0);
+    let mut samples = chunked.render(120, |_| 1.0);
+    samples.extend(chunked.render(180, |_| 1.0));
+    assert_eq!(samples, expected);
+
+    // skipping lands on the phase reached by rendering
+    let mut skipped = chunked.clone();
+    skipped.skip(10_000);
+    chunked.render(10_000, |_| 1.0);
+    assert!((skipped.phase() - chunked.phase()).abs() < 1e-9);
+
+    // ending at a zero crossing lets the next chunk start from silence
+    let extra = chunked.samples_to_zero_crossing();
+    let tail = chunked.render(extra, |_| 1.0);
+    assert!(extra > 0 && extra <= 37);
+    assert!(tail.last().unwrap().abs() < 0.1);
+    assert!(chunked.next_sample().abs() < 0.1);
+
+    // going back to the previous crossing
+    let mut back = Oscillator::new(441.0);
+    back.skip(4410);
+    let since = back.samples_since_zero_crossing();
+    assert!(since > 0 && since <= 50);
+    let mut before = Oscillator::new(441.0);
+    let tail = before.render(4410 - since, |_| 1.0);
+    assert!(tail.last().unwrap().abs() < 0.1);
+}
@@ -0,0 +1,145 @@
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+/// Shape of the wave of an oscillator.
+///
+/// Square, triangle and sawtooth waves have harmonics above the Nyquist frequency that
+/// alias back in the audible range, their band-limited versions remove them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+    Noise,
+    BandLimitedSquare,
+    BandLimitedTriangle,
+    BandLimitedSawtooth,
+}
+
+impl FromStr for Waveform {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sine" => Ok(Waveform::Sine),
+            "square" => Ok(Waveform::Square),
+            "triangle" => Ok(Waveform::Triangle),
+            "sawtooth" | "saw" => Ok(Waveform::Sawtooth),
+            "noise" => Ok(Waveform::Noise),
+            "square-bl" => Ok(Waveform::BandLimitedSquare),
+            "triangle-bl" => Ok(Waveform::BandLimitedTriangle),
+            "sawtooth-bl" | "saw-bl" => Ok(Waveform::BandLimitedSawtooth),
+            _ => Err(format!("Waveform not found: {}", s)),
+        }
+    }
+}
+
+/// Correction of a unit step at `t` = 0 for a wave advancing by `dt` every sample (PolyBLEP).
+fn poly_blep(t: f64, dt: f64) -> f64 {
+    match t {
+        t if t < dt => {
+            let x = t / dt;
+            2.0 * x - x * x - 1.0
+        }
+        t if t > 1.0 - dt => {
+            let x = (t - 1.0) / dt;
+            x * x + 2.0 * x + 1.0
+        }
+        _ => 0.0,
+    }
+}
+
+impl Waveform {
+    /// Whether the wave is 0 at every half period, where a tone can stop without a click.
+    /// Square waves are at full scale there, sawtooth waves jump and noise has no phase.
+    pub fn is_zero_at_half_periods(&self) -> bool {
+        matches!(
+            self,
+            Waveform::Sine | Waveform::Triangle | Waveform::BandLimitedTriangle
+        )
+    }
+
+    /// Value of the periodic waveforms at `phase` radians, in [-1, 1].
+    /// `dt` is the fraction of period advanced every sample.
+    ///
+    /// Sine and triangle waves start from 0 rising, sawtooth waves start from 0 and
+    /// jump at half period, square waves start at 1.
+    pub fn sample(&self, phase: f64, dt: f64) -> f64 {
+        let t = (phase / (2.0 * PI)).rem_euclid(1.0);
+        // sawtooth shifted to start from 0, it jumps at half period
+        let saw_t = (t + 0.5) % 1.0;
+        match self {
+            Waveform::Sine => f64::sin(phase),
+            Waveform::Square | Waveform::BandLimitedSquare => {
+                let square = if t < 0.5 { 1.0 } else { -1.0 };
+                match self {
+                    Waveform::BandLimitedSquare => {
+                        square + poly_blep(t, dt) - poly_blep((t + 0.5) % 1.0, dt)
+                    }
+                    _ => square,
+                }
+            }
+            Waveform::Triangle => match t {
+                t if t < 0.25 => 4.0 * t,
+                t if t < 0.75 => 2.0 - 4.0 * t,
+                t => 4.0 * t - 4.0,
+            },
+            Waveform::Sawtooth => 2.0 * saw_t - 1.0,
+            Waveform::BandLimitedSawtooth => 2.0 * saw_t - 1.0 - poly_blep(saw_t, dt),
+            Waveform::BandLimitedTriangle => {
+                // odd harmonics, falling with the square of their order, up to Nyquist
+                let mut sum = 0_f64;
+                let mut harmonic = 1;
+                while harmonic as f64 * dt < 0.5 {
+                    let sign = if harmonic % 4 == 1 { 1.0 } else { -1.0 };
+                    sum += sign * f64::sin(harmonic as f64 * phase) / (harmonic * harmonic) as f64;
+                    harmonic += 2;
+                }
+                8.0 / (PI * PI) * sum
+            }
+            // noise has no period, the oscillator generates it
+            Waveform::Noise => 0.0,
+        }
+    }
+}
+
+#[test]
+fn test_band_limited_aliasing() {
+    // power of the wave at a frequency, from a direct DFT
+    fn power(samples: &[f64], frequency: f64, sample_rate: f64) -> f64 {
+        let (re, im) = samples
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (i, x)| {
+                let w = 2.0 * PI * frequency * i as f64 / sample_rate;
+                (re + x * f64::cos(w), im + x * f64::sin(w))
+            });
+        re * re + im * im
+    }
+
+    // a 4 kHz wave at 44.1 kHz: the 11th harmonic at 44 kHz aliases to 100 Hz
+    let dt = 4000.0 / 44100.0;
+    let wave = |waveform: Waveform| {
+        (0..4410)
+            .map(|i| waveform.sample(2.0 * PI * dt * i as f64, dt))
+            .collect::<Vec<f64>>()
+    };
+    for (naive, band_limited) in [
+        (Waveform::Square, Waveform::BandLimitedSquare),
+        (Waveform::Sawtooth, Waveform::BandLimitedSawtooth),
+        (Waveform::Triangle, Waveform::BandLimitedTriangle),
+    ] {
+        let alias = |waveform| power(&wave(waveform), 100.0, 44100.0);
+        let fundamental = |waveform| power(&wave(waveform), 4000.0, 44100.0);
+        assert!(
+            alias(band_limited) * 10.0 < alias(naive),
+            "{:?} still aliases",
+            band_limited
+        );
+        assert!(fundamental(band_limited) > 0.8 * fundamental(naive));
+    }
+    assert_eq!(
+        Waveform::from_str("saw-bl"),
+        Ok(Waveform::BandLimitedSawtooth)
+    );
+}
@@ -0,0 +1,208 @@
+use crate::audio::LetterSpan;
+use crate::utils::format_from_extension;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Format of the subtitles file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+}
+
+impl FromStr for SubtitleFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "srt" => Ok(SubtitleFormat::Srt),
+            "vtt" | "webvtt" => Ok(SubtitleFormat::WebVtt),
+            _ => Err(format!("Subtitle format not found: {}", s)),
+        }
+    }
+}
+
+impl SubtitleFormat {
+    /// Format given by the extension of the file, SRT when it isn't known.
+    pub fn from_file_name(file_name: &str) -> Self {
+        format_from_extension(file_name).unwrap_or(SubtitleFormat::Srt)
+    }
+}
+
+/// How much of the message every caption shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Character,
+    Word,
+    Line,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "char" | "character" => Ok(Granularity::Character),
+            "word" => Ok(Granularity::Word),
+            "line" => Ok(Granularity::Line),
+            _ => Err(format!("Subtitle granularity not found: {}", s)),
+        }
+    }
+}
+
+/// Text shown from the sample `start` of the audio up to the sample `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caption {
+    pub text: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Captions of the letters keyed in the audio, grouped by `granularity`.
+///
+/// `line_starts` are the indices of the letters, spaces included, where every line
+/// of the message starts, in increasing order.
+pub fn captions(
+    spans: &[LetterSpan],
+    granularity: Granularity,
+    line_starts: &[usize],
+) -> Vec<Caption> {
+    let line = |span: &LetterSpan| line_starts.partition_point(|start| *start <= span.index);
+    let mut captions: Vec<Caption> = Vec::new();
+    let mut last: Option<&LetterSpan> = None;
+    for span in spans {
+        let same_caption = last.is_some_and(|last| match granularity {
+            Granularity::Character => false,
+            Granularity::Word => last.word == span.word,
+            Granularity::Line => line(last) == line(span),
+        });
+        match captions.last_mut() {
+            Some(caption) if same_caption => {
+                if last.is_some_and(|last| last.word != span.word) {
+                    caption.text.push(' ');
+                }
+                caption.text.push_str(&span.text);
+                caption.end = span.end;
+            }
+            _ => captions.push(Caption {
+                text: span.text.clone(),
+                start: span.start,
+                end: span.end,
+            }),
+        }
+        last = Some(span);
+    }
+    captions
+}
+
+/// Time of a sample, rounded to the millisecond, as HH:MM:SS followed by
+/// `separator` and the milliseconds.
+fn timestamp(sample: u64, sample_rate: u32, separator: char) -> String {
+    let sample_rate = sample_rate as u64;
+    let millis = (sample * 1000 + sample_rate / 2) / sample_rate;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+/// Write the captions of audio sampled at `sample_rate` as a subtitles file.
+pub fn write_subtitles(
+    captions: &[Caption],
+    sample_rate: u32,
+    format: SubtitleFormat,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let separator = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::WebVtt => {
+            writer.write_all(b"WEBVTT\n\n")?;
+            '.'
+        }
+    };
+    for (i, caption) in captions.iter().enumerate() {
+        // cues are numbered from 1, WebVTT identifiers are optional
+        if format == SubtitleFormat::Srt {
+            writeln!(writer, "{}", i + 1)?;
+        }
+        writeln!(
+            writer,
+            "{} --> {}\n{}\n",
+            timestamp(caption.start, sample_rate, separator),
+            timestamp(caption.end, sample_rate, separator),
+            caption.text
+        )?;
+    }
+    writer.flush()
+}
+
+#[test]
+fn test_captions() {
+    use crate::audio::AudioSettings;
+    use crate::morse_alphabet::{E, SPACE, T};
+    use crate::Letter;
+
+    // two lines: "et e" and "t"
+    let letters = [SPACE, E, T, SPACE, E, SPACE, T];
+    let line_starts = [0, 5];
+    let settings = AudioSettings::default();
+    let source = settings.source(letters);
+    let samples = Letter::concat_audio(letters.into_iter(), &settings);
+    let text = |granularity: Granularity| {
+        captions(source.letter_spans(), granularity, &line_starts)
+            .into_iter()
+            .map(|caption| caption.text)
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(text(Granularity::Character), vec!["e", "t", "e", "t"]);
+    assert_eq!(text(Granularity::Word), vec!["et", "e", "t"]);
+    assert_eq!(text(Granularity::Line), vec!["et e", "t"]);
+
+    // the audio is silent between the captions and keyed within them
+    let characters = captions(source.letter_spans(), Granularity::Character, &line_starts);
+    let mut silence_start = 0;
+    for caption in characters.iter() {
+        let (start, end) = (caption.start as usize, caption.end as usize);
+        assert!(samples[silence_start..start]
+            .iter()
+            .all(|sample| *sample == 0.0));
+        assert!(samples[start..end].iter().any(|sample| *sample != 0.0));
+        silence_start = end;
+    }
+    assert!(samples[silence_start..].iter().all(|sample| *sample == 0.0));
+
+    let lines = captions(source.letter_spans(), Granularity::Line, &line_starts);
+    assert_eq!(lines[0].start, characters[0].start);
+    assert_eq!(lines[0].end, characters[2].end);
+
+    let mut srt: Vec<u8> = Vec::new();
+    let caption = Caption {
+        text: String::from("sos"),
+        start: 44100 * 3661 + 22050,
+        end: 44100 * 3662,
+    };
+    write_subtitles(
+        std::slice::from_ref(&caption),
+        44100,
+        SubtitleFormat::Srt,
+        &mut srt,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(srt).unwrap(),
+        "1\n01:01:01,500 --> 01:01:02,000\nsos\n\n"
+    );
+    let mut vtt: Vec<u8> = Vec::new();
+    write_subtitles(&[caption], 44100, SubtitleFormat::WebVtt, &mut vtt).unwrap();
+    assert_eq!(
+        String::from_utf8(vtt).unwrap(),
+        "WEBVTT\n\n01:01:01.500 --> 01:01:02.000\nsos\n\n"
+    );
+    assert_eq!(
+        SubtitleFormat::from_file_name("out/morse.VTT"),
+        SubtitleFormat::WebVtt
+    );
+    assert_eq!(SubtitleFormat::from_file_name("-"), SubtitleFormat::Srt);
+}
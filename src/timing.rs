@@ -0,0 +1,231 @@
+use crate::Letter;
+
+/// Speed used when no other is given, a dot lasts 100 ms.
+pub const DEFAULT_WPM: f32 = 12.0;
+
+/// Delay in seconds to add to the gaps of every word so that characters sent at
+/// `char_wpm` have an overall speed of `effective_wpm`, following the ARRL standard.
+///
+/// PARIS is made of 31 dots of elements and gaps inside letters, sent at `char_wpm`,
+/// and 19 dots of gaps between letters and words, that fill the rest of the word.
+pub fn arrl_farnsworth_delay(char_wpm: f32, effective_wpm: f32) -> f64 {
+    let (char_wpm, effective_wpm) = (char_wpm as f64, effective_wpm as f64);
+    (60.0 * char_wpm - 37.2 * effective_wpm) / (char_wpm * effective_wpm)
+}
+
+/// Duration in seconds of the elements of morse code and of the gaps between them.
+///
+/// Durations follow the PARIS standard: a dash lasts 3 dots, elements of a letter are
+/// separated by 1 dot, letters by 3 and words by 7. The word "PARIS " is 50 dots long,
+/// so at `wpm` words per minute a dot lasts 1.2 / `wpm` seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MorseTiming {
+    pub dot: f64,
+    pub dash: f64,
+    pub element_gap: f64,
+    pub letter_gap: f64,
+    pub word_gap: f64,
+}
+
+impl Default for MorseTiming {
+    fn default() -> Self {
+        Self::from_wpm(DEFAULT_WPM)
+    }
+}
+
+impl MorseTiming {
+    pub fn from_wpm(wpm: f32) -> Self {
+        let unit = 1.2 / wpm as f64;
+        MorseTiming {
+            dot: unit,
+            dash: 3.0 * unit,
+            element_gap: unit,
+            letter_gap: 3.0 * unit,
+            word_gap: 7.0 * unit,
+        }
+    }
+
+    /// Farnsworth timing: elements sent at `char_wpm`, letter and word gaps stretched
+    /// to slow the overall speed down to `effective_wpm`.
+    pub fn farnsworth(char_wpm: f32, effective_wpm: f32) -> Self {
+        let timing = Self::from_wpm(char_wpm);
+        if effective_wpm >= char_wpm {
+            return timing;
+        }
+        let delay = arrl_farnsworth_delay(char_wpm, effective_wpm);
+        MorseTiming {
+            letter_gap: 3.0 * delay / 19.0,
+            word_gap: 7.0 * delay / 19.0,
+            ..timing
+        }
+    }
+
+    /// Speed in words per minute of the elements.
+    pub fn wpm(&self) -> f32 {
+        (1.2 / self.dot) as f32
+    }
+
+    /// Tones and silences to key the letters: elements of a letter are separated by
+    /// element gaps, letters by letter gaps and spaces become word gaps.
+    /// There is no silence before the first tone or after the last one.
+    pub fn keying<'a, T: IntoIterator<Item = Letter<'a>>>(&self, letters: T) -> Vec<KeyingElement> {
+        self.keying_letters(letters)
+            .into_iter()
+            .flat_map(|(_, elements)| elements)
+            .collect()
+    }
+
+    /// Keying of every letter, starting with the silence that precedes its first tone.
+    /// Spaces have no elements, they stretch the silence before the next letter.
+    pub fn keying_letters<'a, T: IntoIterator<Item = Letter<'a>>>(
+        &self,
+        letters: T,
+    ) -> Vec<(Letter<'a>, Vec<KeyingElement>)> {
+        let mut keyed_letters: Vec<(Letter<'a>, Vec<KeyingElement>)> = Vec::new();
+        // silence due before the next tone, none before the first one
+        let mut gap: Option<f64> = None;
+
+        for letter in letters {
+            let Letter(_, morse) = letter;
+            let mut elements: Vec<KeyingElement> = Vec::new();
+            if morse == "/" {
+                gap = gap.map(|_| self.word_gap);
+                keyed_letters.push((letter, elements));
+                continue;
+            }
+            for symbol in morse.chars() {
+                let duration = match symbol {
+                    '.' => self.dot,
+                    '-' => self.dash,
+                    _ => continue,
+                };
+                if let Some(gap) = gap {
+                    elements.push(KeyingElement {
+                        tone: false,
+                        duration: gap,
+                    });
+                }
+                elements.push(KeyingElement {
+                    tone: true,
+                    duration,
+                });
+                gap = Some(self.element_gap);
+            }
+            gap = gap.map(|_| self.letter_gap);
+            keyed_letters.push((letter, elements));
+        }
+        keyed_letters
+    }
+}
+
+/// Tone or silence lasting `duration` seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyingElement {
+    pub tone: bool,
+    pub duration: f64,
+}
+
+/// Timeline of the keying counted in samples.
+///
+/// Every element starts at the sample nearest to its exact time from the start of the
+/// message: the fraction of a sample an element lasts beyond its whole samples is carried
+/// to the next one, so the lengths differ by a sample and the timeline never drifts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleTimeline {
+    sample_rate: f64,
+    position: u64,
+    // the exact time is `position + remainder` samples, with the remainder in [-0.5, 0.5]
+    remainder: f64,
+}
+
+impl SampleTimeline {
+    pub fn new(sample_rate: u32) -> Self {
+        SampleTimeline {
+            sample_rate: sample_rate as f64,
+            position: 0,
+            remainder: 0.0,
+        }
+    }
+
+    /// Samples of an element lasting `duration` seconds, following the previous one.
+    pub fn advance(&mut self, duration: f64) -> u64 {
+        let exact = duration * self.sample_rate + self.remainder;
+        let samples = exact.round().max(0.0);
+        self.remainder = exact - samples;
+        self.position += samples as u64;
+        samples as u64
+    }
+
+    /// Samples elapsed since the start of the message.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+#[test]
+fn test_paris_timing() {
+    let timing = MorseTiming::from_wpm(20.0);
+    // PARIS: .--. .- .-. .. ... plus the word gap
+    let paris = 10.0 * timing.dot
+        + 4.0 * timing.dash
+        + 9.0 * timing.element_gap
+        + 4.0 * timing.letter_gap
+        + timing.word_gap;
+    assert!((paris - 60.0 / 20.0).abs() < 1e-5);
+    assert!((MorseTiming::default().dot - 0.1).abs() < 1e-6);
+    assert!((timing.wpm() - 20.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_farnsworth_timing() {
+    let timing = MorseTiming::farnsworth(20.0, 10.0);
+    let paris = 10.0 * timing.dot
+        + 4.0 * timing.dash
+        + 9.0 * timing.element_gap
+        + 4.0 * timing.letter_gap
+        + timing.word_gap;
+    assert!((paris - 60.0 / 10.0).abs() < 1e-5);
+    assert_eq!(timing.dot, MorseTiming::from_wpm(20.0).dot);
+    assert!((timing.word_gap / timing.letter_gap - 7.0 / 3.0).abs() < 1e-5);
+    assert_eq!(
+        MorseTiming::farnsworth(15.0, 18.0),
+        MorseTiming::from_wpm(15.0)
+    );
+}
+
+#[test]
+fn test_sample_timeline() {
+    use crate::morse_alphabet::{A, I, P, R, S, SPACE};
+
+    // an hour of PARIS at 13 wpm, where a unit isn't a whole number of samples
+    let timing = MorseTiming::from_wpm(13.0);
+    let words = 60 * 13;
+    let keying = timing.keying(std::iter::repeat_n([P, A, R, I, S, SPACE], words).flatten());
+    let mut timeline = SampleTimeline::new(44100);
+    let total: u64 = keying
+        .iter()
+        .map(|element| timeline.advance(element.duration))
+        .sum();
+    // the message ends with the last tone, without the word gap
+    let units = 50 * words as u64 - 7;
+    assert_eq!(timeline.position(), total);
+    // a unit lasts 1.2 / 13 seconds, rounded to the nearest sample
+    assert_eq!(total, (units * 44100 * 12 + 65) / 130);
+
+    // truncating every element would lose a fraction of a sample each time
+    let truncated: u64 = keying
+        .iter()
+        .map(|element| (element.duration * 44100.0) as u64)
+        .sum();
+    assert!(truncated < total);
+
+    // at 12 wpm a unit is exactly 4410 samples
+    let mut timeline = SampleTimeline::new(44100);
+    assert_eq!(
+        (0..36_000)
+            .map(|_| timeline.advance(MorseTiming::default().dot))
+            .collect::<std::collections::BTreeSet<u64>>(),
+        std::collections::BTreeSet::from([4410])
+    );
+    assert_eq!(timeline.position(), 3600 * 44100);
+}
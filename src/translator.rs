@@ -1,8 +1,17 @@
-use crate::parser::{MorseCommand, MorseTraductionType};
-use crate::polyphonia::SAMPLE_RATE;
+use crate::audio::{AudioSettings, LetterSpan, MorseAudioSource};
+use crate::decoder::{parse_key_log, Corrector, Dictionary, Segmenter, TimingClassifier};
+use crate::flac::{check_spec, write_flac};
+use crate::lint::{self, Severity};
+use crate::midi::{morse_events, write_smf, MidiSettings};
+use crate::parser::{AudioFormat, DecodedFormat, MorseCommand, MorseTraductionType};
+use crate::polyphonia::{Amplitude, Envelope, EnvelopeShape, Note, Waveform};
+use crate::subtitles::{captions, write_subtitles, Granularity, SubtitleFormat};
+use crate::timing::MorseTiming;
 use crate::utils::{get_reader, get_writer};
-use crate::wav::write_wav;
-use crate::Letter;
+use crate::wav::{
+    write_aiff, write_au, write_raw, Cue, SampleFormat, WavMetadata, WavSpec, WavWriter,
+};
+use crate::{morse_alphabet, Letter};
 use std::cell::RefCell;
 use std::default::Default;
 use std::error::Error;
@@ -18,6 +27,12 @@ pub trait MorseTranslator<T, W, R> {
 
     fn translate_to_audio(&mut self, command: MorseCommand) -> Result<R, Box<dyn Error>>;
 
+    fn translate_to_midi(&mut self, command: MorseCommand) -> Result<R, Box<dyn Error>>;
+
+    fn translate_from_timing(&mut self, command: MorseCommand) -> Result<R, Box<dyn Error>>;
+
+    fn check(&mut self) -> Result<R, Box<dyn Error>>;
+
     fn encode(raw_data: T) -> W;
 
     fn decode(raw_data: T) -> W;
@@ -34,14 +49,36 @@ pub struct StreamedMorseTranslator<'a> {
     // or an AudioMorseTranslation trasparently
     input_stream: Option<Vec<String>>,
     pub output_stream: Option<Rc<RefCell<dyn Write + 'a>>>,
+    pub subtitle_stream: Option<Rc<RefCell<dyn Write + 'a>>>,
+    pub subtitle_format: SubtitleFormat,
+    pub subtitle_granularity: Granularity,
     pub traduction_type: MorseTraductionType,
+    pub audio_settings: AudioSettings,
+    pub audio_format: AudioFormat,
+    pub title: Option<String>,
+    pub sample_format: SampleFormat,
+    pub tempo: Option<f32>,
+    pub velocity: u8,
+    pub decoded_format: DecodedFormat,
+    pub confidence_marker: String,
+    pub confidence_threshold: f32,
+    pub segment_candidates: Option<usize>,
+    pub correct: bool,
+    dictionary: Dictionary,
 }
 
-impl<'l> MorseTranslator<&str, Vec<Letter<'l>>, ()> for StreamedMorseTranslator<'_> {
+impl<'l> MorseTranslator<&str, Result<Vec<Letter<'l>>, String>, ()>
+    for StreamedMorseTranslator<'_>
+{
     fn translate(&mut self, command: MorseCommand) -> Result<(), Box<dyn Error>> {
+        if let MorseCommand::Check = command {
+            return self.check();
+        }
         match self.traduction_type {
             MorseTraductionType::Text => self.translate_to_text(command),
             MorseTraductionType::Audio => self.translate_to_audio(command),
+            MorseTraductionType::Midi => self.translate_to_midi(command),
+            MorseTraductionType::Timing => self.translate_from_timing(command),
         }
     }
 
@@ -49,45 +86,218 @@ impl<'l> MorseTranslator<&str, Vec<Letter<'l>>, ()> for StreamedMorseTranslator<
         let read_cmd = match command {
             MorseCommand::Encode => Self::encode,
             MorseCommand::Decode => Self::decode,
+            MorseCommand::Check => return self.check(),
         };
+        let nyquist = self.audio_settings.sample_rate as f32 / 2.0;
+        if self.audio_settings.tone.get_frequency() >= nyquist {
+            return Err(format!(
+                "Tone of {} Hz can't be rendered at {} Hz, it must be under {} Hz",
+                self.audio_settings.tone.get_frequency(),
+                self.audio_settings.sample_rate,
+                nyquist
+            )
+            .into());
+        }
 
-        let translated_lines = self
-            .input_stream
-            .as_ref()
-            .expect("Input stream not initialized, failing.")
+        let lines = self.read_lines(read_cmd)?;
+        // lines are separated by a word gap, like words
+        let translated_lines = lines
             .iter()
-            .flat_map(|line| read_cmd(line));
+            .flat_map(|line| std::iter::once(morse_alphabet::SPACE).chain(line.iter().copied()));
+        let source = self.audio_settings.source(translated_lines);
+        let spec = WavSpec::mono(self.audio_settings.sample_rate, self.sample_format);
+        // unsupported specs fail before anything is rendered or written
+        match self.audio_format {
+            AudioFormat::Wav => spec.check()?,
+            AudioFormat::Flac => {
+                check_spec(&spec)?;
+            }
+            _ => {}
+        }
+        if let Some(subtitle_stream) = self.subtitle_stream.as_ref() {
+            // every line starts with the space added before it
+            let line_starts = lines
+                .iter()
+                .scan(0, |start, line| {
+                    let line_start = *start;
+                    *start += line.len() + 1;
+                    Some(line_start)
+                })
+                .collect::<Vec<usize>>();
+            write_subtitles(
+                &captions(
+                    source.letter_spans(),
+                    self.subtitle_granularity,
+                    &line_starts,
+                ),
+                source.sample_rate(),
+                self.subtitle_format,
+                subtitle_stream.borrow_mut().deref_mut(),
+            )?;
+        }
+
+        let mut output = self
+            .output_stream
+            .as_ref()
+            .expect("Output stream not inizialized, failing.")
+            .borrow_mut();
+        match self.audio_format {
+            AudioFormat::Wav => {
+                // samples are streamed to the output as they are rendered
+                let message = lines
+                    .iter()
+                    .map(|line| {
+                        String::from_utf8_lossy(&Letter::concat_text(line.clone())).into_owned()
+                    })
+                    .collect::<Vec<String>>();
+                let mut writer = WavWriter::with_metadata(
+                    output.deref_mut(),
+                    spec,
+                    source.len() as u64,
+                    &self.wav_metadata(&message, &source),
+                )?;
+                writer.write_samples(source)?;
+                writer.finalize()?
+            }
+            AudioFormat::Aiff => write_aiff(source, &spec, output.deref_mut())?,
+            AudioFormat::Au => write_au(source, &spec, output.deref_mut())?,
+            AudioFormat::Flac => write_flac(source, &spec, output.deref_mut())?,
+            AudioFormat::Raw(format) => {
+                // the same samples as Letter::concat_audio
+                let samples = source.collect::<Vec<f32>>();
+                // there is no header, the consumer has to be told how to read the samples
+                eprintln!(
+                    "Raw PCM: {}, {} Hz, 1 channel",
+                    format, self.audio_settings.sample_rate
+                );
+                write_raw(&samples, format, output.deref_mut())?
+            }
+        }
+        output.flush()?;
+        Ok(())
+    }
+
+    fn translate_to_midi(&mut self, command: MorseCommand) -> Result<(), Box<dyn Error>> {
+        let read_cmd = match command {
+            MorseCommand::Encode => Self::encode,
+            MorseCommand::Decode => Self::decode,
+            MorseCommand::Check => return self.check(),
+        };
+
+        // lines are separated by a word gap, like in the audio
+        let lines = self.read_lines(read_cmd)?;
+        let keying = self.audio_settings.timing.keying(
+            lines
+                .into_iter()
+                .flat_map(|line| std::iter::once(morse_alphabet::SPACE).chain(line)),
+        );
+        let dot = self.audio_settings.timing.dot;
+        let settings = MidiSettings {
+            bpm: self.tempo.unwrap_or(MidiSettings::bpm_of_dot(dot)),
+            note: self.audio_settings.tone.midi_number(),
+            velocity: self.velocity,
+        };
+
         let mut output = self
             .output_stream
             .as_ref()
             .expect("Output stream not inizialized, failing.")
             .borrow_mut();
-        write_wav(
-            Letter::concat_audio(translated_lines),
-            SAMPLE_RATE,
-            output.deref_mut(),
+        write_smf(&morse_events(&keying, dot, &settings)?, output.deref_mut())?;
+        Ok(())
+    }
+
+    fn translate_from_timing(&mut self, command: MorseCommand) -> Result<(), Box<dyn Error>> {
+        if let MorseCommand::Encode = command {
+            return Err("Timing traduction supports only the decode command".into());
+        }
+
+        let intervals = parse_key_log(
+            self.input_stream
+                .as_ref()
+                .expect("Input stream not initialized, failing."),
         )?;
+        let letters = TimingClassifier::from_intervals(&intervals).decode_timed(&intervals)?;
+
+        let mut output = self
+            .output_stream
+            .as_ref()
+            .expect("Output stream not inizialized, failing.")
+            .borrow_mut();
+        match self.decoded_format {
+            DecodedFormat::Text => {
+                for decoded in letters {
+                    let Letter(text, _) = decoded.letter;
+                    output.write_all(text.as_bytes())?;
+                    if decoded.confidence < self.confidence_threshold {
+                        output.write_all(self.confidence_marker.as_bytes())?;
+                    }
+                }
+            }
+            DecodedFormat::JsonLines => {
+                for decoded in letters {
+                    writeln!(output, "{}", decoded.json_line())?;
+                }
+            }
+        }
         output.flush()?;
         Ok(())
     }
 
+    fn check(&mut self) -> Result<(), Box<dyn Error>> {
+        let issues = lint::check(
+            self.input_stream
+                .as_ref()
+                .expect("Input stream not initialized, failing."),
+        );
+        let errors = issues
+            .iter()
+            .filter(|issue| issue.kind.severity() == Severity::Error)
+            .count();
+
+        let mut output = self
+            .output_stream
+            .as_ref()
+            .expect("Output stream not inizialized, failing.")
+            .borrow_mut();
+        for issue in issues.iter() {
+            writeln!(output, "{}", issue)?;
+        }
+        writeln!(
+            output,
+            "{} errors, {} warnings",
+            errors,
+            issues.len() - errors
+        )?;
+        output.flush()?;
+
+        match errors {
+            0 => Ok(()),
+            _ => Err(format!("Morse check failed with {} errors", errors).into()),
+        }
+    }
+
     fn translate_to_text(&mut self, command: MorseCommand) -> Result<(), Box<dyn Error>> {
+        if let (MorseCommand::Decode, Some(candidates)) = (&command, self.segment_candidates) {
+            return self.translate_segmented(candidates);
+        }
+        if let (MorseCommand::Decode, true) = (&command, self.correct) {
+            return self.translate_corrected();
+        }
+
         let read_cmd = match command {
             MorseCommand::Encode => Self::encode,
             MorseCommand::Decode => Self::decode,
+            MorseCommand::Check => return self.check(),
         };
 
         let translate_cmd = match command {
             MorseCommand::Encode => Letter::concat_morse,
             MorseCommand::Decode => Letter::concat_text,
+            MorseCommand::Check => return self.check(),
         };
 
-        let translated_lines = self
-            .input_stream
-            .as_ref()
-            .expect("Input stream not initialized, failing.")
-            .iter()
-            .map(|line| read_cmd(line));
+        let translated_lines = self.read_lines(read_cmd)?.into_iter();
 
         let mut output = self
             .output_stream
@@ -105,24 +315,23 @@ impl<'l> MorseTranslator<&str, Vec<Letter<'l>>, ()> for StreamedMorseTranslator<
         Ok(())
     }
 
-    fn encode(line: &str) -> Vec<Letter<'l>> {
-        line.bytes()
-            .map(
-                |byte| match Letter::from_str(str::from_utf8(&[byte]).unwrap()) {
-                    Ok(letter) => letter,
-                    Err(err) => panic!("Character not supported {:?}", err),
-                },
-            )
-            .collect::<Vec<Letter<'_>>>()
+    fn encode(line: &str) -> Result<Vec<Letter<'l>>, String> {
+        line.chars()
+            .enumerate()
+            .map(|(i, ch)| {
+                Letter::from_str(&ch.to_string())
+                    .map_err(|err| format!("{} (character {})", err, i + 1))
+            })
+            .collect()
     }
 
-    fn decode(line: &str) -> Vec<Letter<'l>> {
+    fn decode(line: &str) -> Result<Vec<Letter<'l>>, String> {
         line.split_whitespace()
-            .map(|morse_letter| match Letter::from_str(morse_letter) {
-                Ok(letter) => letter,
-                Err(err) => panic!("Character not supported {:?}", err),
+            .enumerate()
+            .map(|(i, morse_letter)| {
+                Letter::from_str(morse_letter).map_err(|err| format!("{} (letter {})", err, i + 1))
             })
-            .collect::<Vec<Letter<'_>>>()
+            .collect()
     }
 }
 
@@ -137,7 +346,22 @@ impl<'a> StreamedMorseTranslator<'a> {
         StreamedMorseTranslator {
             input_stream: None,
             output_stream: None,
+            subtitle_stream: None,
+            subtitle_format: SubtitleFormat::Srt,
+            subtitle_granularity: Granularity::Word,
             traduction_type: MorseTraductionType::Text,
+            audio_settings: AudioSettings::default(),
+            audio_format: AudioFormat::Wav,
+            title: None,
+            sample_format: SampleFormat::S16,
+            tempo: None,
+            velocity: 100,
+            decoded_format: DecodedFormat::Text,
+            confidence_marker: String::from("?"),
+            confidence_threshold: 0.5,
+            segment_candidates: None,
+            correct: false,
+            dictionary: Dictionary::default(),
         }
     }
 
@@ -150,7 +374,7 @@ impl<'a> StreamedMorseTranslator<'a> {
         self.input_stream = Some(
             get_reader(input_filename)
                 .lines()
-                .flatten()
+                .map_while(Result::ok)
                 .collect::<Vec<String>>(),
         );
         self
@@ -161,8 +385,32 @@ impl<'a> StreamedMorseTranslator<'a> {
         self
     }
 
+    /// Write the output to a file, audio in the container given by its extension.
     pub fn out_file(&mut self, output_filename: &str) -> &mut Self {
         self.output_stream = Some(Rc::new(RefCell::new(get_writer(output_filename))));
+        self.audio_format = AudioFormat::from_file_name(output_filename);
+        self
+    }
+
+    /// Write subtitles of the audio to a file, in the format given by its extension.
+    pub fn subtitle_file(&mut self, subtitle_filename: &str) -> &mut Self {
+        self.subtitle_stream = Some(Rc::new(RefCell::new(get_writer(subtitle_filename))));
+        self.subtitle_format = SubtitleFormat::from_file_name(subtitle_filename);
+        self
+    }
+
+    pub fn subtitle_stream(&mut self, subtitle_stream: Rc<RefCell<dyn Write>>) -> &mut Self {
+        self.subtitle_stream = Some(subtitle_stream);
+        self
+    }
+
+    pub fn subtitle_format(&mut self, subtitle_format: SubtitleFormat) -> &mut Self {
+        self.subtitle_format = subtitle_format;
+        self
+    }
+
+    pub fn subtitle_granularity(&mut self, subtitle_granularity: Granularity) -> &mut Self {
+        self.subtitle_granularity = subtitle_granularity;
         self
     }
 
@@ -170,4 +418,259 @@ impl<'a> StreamedMorseTranslator<'a> {
         self.traduction_type = traduction_type;
         self
     }
+
+    pub fn wpm(&mut self, wpm: f32) -> &mut Self {
+        self.audio_settings.timing = MorseTiming::from_wpm(wpm);
+        self
+    }
+
+    /// Stretch the gaps between letters and words so that the overall speed becomes
+    /// `effective_wpm`, keeping the speed of the letters set with [Self::wpm].
+    pub fn effective_wpm(&mut self, effective_wpm: f32) -> &mut Self {
+        self.audio_settings.timing =
+            MorseTiming::farnsworth(self.audio_settings.timing.wpm(), effective_wpm);
+        self
+    }
+
+    pub fn tone(&mut self, tone: Note) -> &mut Self {
+        self.audio_settings.tone = tone;
+        self
+    }
+
+    pub fn volume(&mut self, volume: Amplitude) -> &mut Self {
+        self.audio_settings.volume = volume;
+        self
+    }
+
+    /// Shape the attack and release of the tone, `rise` is in seconds.
+    pub fn envelope(&mut self, shape: EnvelopeShape, rise: f32) -> &mut Self {
+        self.audio_settings.envelope = Envelope { shape, rise };
+        self
+    }
+
+    pub fn waveform(&mut self, waveform: Waveform) -> &mut Self {
+        self.audio_settings.waveform = waveform;
+        self
+    }
+
+    pub fn sample_rate(&mut self, sample_rate: u32) -> &mut Self {
+        self.audio_settings.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn audio_format(&mut self, audio_format: AudioFormat) -> &mut Self {
+        self.audio_format = audio_format;
+        self
+    }
+
+    /// Title written in the metadata of the audio, the first line of the message if not set.
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.title = Some(title.to_owned());
+        self
+    }
+
+    pub fn sample_format(&mut self, sample_format: SampleFormat) -> &mut Self {
+        self.sample_format = sample_format;
+        self
+    }
+
+    /// Tempo of MIDI output in quarter notes per minute, a dot lasts a sixteenth note.
+    /// Without a tempo the rhythm plays at the speed of the audio.
+    pub fn tempo(&mut self, tempo: Option<f32>) -> &mut Self {
+        self.tempo = tempo;
+        self
+    }
+
+    pub fn velocity(&mut self, velocity: u8) -> &mut Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn decoded_format(&mut self, decoded_format: DecodedFormat) -> &mut Self {
+        self.decoded_format = decoded_format;
+        self
+    }
+
+    pub fn confidence_marker(&mut self, confidence_marker: &str) -> &mut Self {
+        self.confidence_marker = confidence_marker.to_owned();
+        self
+    }
+
+    pub fn confidence_threshold(&mut self, confidence_threshold: f32) -> &mut Self {
+        self.confidence_threshold = confidence_threshold;
+        self
+    }
+
+    pub fn segment_candidates(&mut self, segment_candidates: Option<usize>) -> &mut Self {
+        self.segment_candidates = segment_candidates;
+        self
+    }
+
+    pub fn correct(&mut self, correct: bool) -> &mut Self {
+        self.correct = correct;
+        self
+    }
+
+    pub fn dictionary_file(&mut self, dictionary_filename: &str) -> &mut Self {
+        self.dictionary = Dictionary::from_words(
+            get_reader(dictionary_filename)
+                .lines()
+                .map_while(Result::ok),
+        );
+        self
+    }
+
+    /// Title, message and a cue at the start of every letter and word of the audio.
+    fn wav_metadata(&self, message: &[String], source: &MorseAudioSource) -> WavMetadata {
+        let letters = source.letter_spans().iter().cloned();
+        // a word of a single letter is already marked by the letter
+        let words = source
+            .word_spans()
+            .into_iter()
+            .filter(|word| word.text.chars().count() > 1);
+        let mut spans = letters.chain(words).collect::<Vec<LetterSpan>>();
+        spans.sort_by_key(|span| span.start);
+
+        WavMetadata {
+            title: self.title.clone().or_else(|| message.first().cloned()),
+            comment: Some(message.join("\n")),
+            software: Some(format!(
+                "{} {}",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            )),
+            cues: spans
+                .into_iter()
+                .filter_map(|span| {
+                    Some(Cue {
+                        frame: u32::try_from(span.start).ok()?,
+                        label: span.text,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Decode every line as morse without letter separators, writing the best readings
+    /// of each line with their score, lines are separated by an empty line.
+    fn translate_segmented(&mut self, candidates: usize) -> Result<(), Box<dyn Error>> {
+        let segmenter = Segmenter::new(&self.dictionary);
+        let mut output = self
+            .output_stream
+            .as_ref()
+            .expect("Output stream not inizialized, failing.")
+            .borrow_mut();
+        for (i, line) in self
+            .input_stream
+            .as_ref()
+            .expect("Input stream not initialized, failing.")
+            .iter()
+            .enumerate()
+        {
+            if i != 0 {
+                output.write_all(b"\n")?;
+            }
+            for reading in segmenter.segment_line(line, candidates)? {
+                writeln!(output, "{:.2}\t{}", reading.score, reading.text)?;
+            }
+        }
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Read every input line with `read_cmd`, the error tells the line that can't be read.
+    fn read_lines<'s, 'l, F>(&'s self, read_cmd: F) -> Result<Vec<Vec<Letter<'l>>>, String>
+    where
+        F: Fn(&'s str) -> Result<Vec<Letter<'l>>, String>,
+    {
+        self.input_stream
+            .as_ref()
+            .expect("Input stream not initialized, failing.")
+            .iter()
+            .enumerate()
+            .map(|(i, line)| read_cmd(line).map_err(|err| format!("Line {}: {}", i + 1, err)))
+            .collect()
+    }
+
+    /// Decode every line correcting mistyped codes, writing the raw and the corrected
+    /// reading, then the nearest letters of every unknown code, separated by tabs.
+    fn translate_corrected(&mut self) -> Result<(), Box<dyn Error>> {
+        let corrector = Corrector::new(&self.dictionary);
+        let mut output = self
+            .output_stream
+            .as_ref()
+            .expect("Output stream not inizialized, failing.")
+            .borrow_mut();
+        for line in self
+            .input_stream
+            .as_ref()
+            .expect("Input stream not initialized, failing.")
+        {
+            let correction = corrector.correct_line(line);
+            write!(output, "{}\t{}", correction.raw, correction.corrected)?;
+            for suggestion in correction.suggestions.iter() {
+                write!(output, "\t{}", suggestion)?;
+            }
+            writeln!(output)?;
+        }
+        output.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_out_file_format() {
+    use std::fs;
+
+    let directory = std::env::temp_dir();
+    let aiff = directory.join("morse_out_file.aiff");
+    let au = directory.join("morse_out_file.snd");
+    let mut translator = StreamedMorseTranslator::default();
+    translator
+        .in_stream(vec![String::from("e")])
+        .traduction_type(MorseTraductionType::Audio);
+
+    // the container follows the extension of the file
+    translator.out_file(aiff.to_str().unwrap());
+    assert_eq!(translator.audio_format, AudioFormat::Aiff);
+    translator.translate(MorseCommand::Encode).unwrap();
+    assert_eq!(&fs::read(&aiff).unwrap()[8..12], b"AIFF");
+
+    // unless it's set explicitly
+    translator
+        .out_file(aiff.to_str().unwrap())
+        .audio_format(AudioFormat::Au);
+    translator.translate(MorseCommand::Encode).unwrap();
+    assert_eq!(&fs::read(&aiff).unwrap()[..4], b".snd");
+
+    translator.out_file(au.to_str().unwrap());
+    assert_eq!(translator.audio_format, AudioFormat::Au);
+    translator.out_file("-");
+    assert_eq!(translator.audio_format, AudioFormat::Wav);
+    fs::remove_file(aiff).unwrap();
+    fs::remove_file(au).unwrap();
+}
+
+#[test]
+fn test_unknown_code() {
+    let out: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+    let mut translator = StreamedMorseTranslator::default();
+    translator
+        .in_stream(vec![String::from(".- -..."), String::from(".- .-.- -...")])
+        .out_stream(out.clone());
+
+    // the error names the code and where it is instead of panicking
+    let err = translator.translate(MorseCommand::Decode).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Line 2: No representation found for the string: .-.- (letter 2)"
+    );
+    assert!(out.borrow().is_empty());
+
+    translator.in_stream(vec![String::from("a#")]);
+    let err = translator.translate(MorseCommand::Encode).unwrap_err();
+    assert!(err.to_string().ends_with(": # (character 2)"));
+
+    translator.traduction_type(MorseTraductionType::Audio);
+    assert!(translator.translate(MorseCommand::Encode).is_err());
 }
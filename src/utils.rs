@@ -1,10 +1,12 @@
 use std::fs::OpenOptions;
 use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
 
 pub fn get_reader(arg: &str) -> Box<dyn BufRead> {
     match arg {
         "-" => Box::new(io::stdin().lock()),
-        x if x.is_empty() => Box::new(io::stdin().lock()),
+        "" => Box::new(io::stdin().lock()),
         file_name => Box::new(BufReader::new(
             OpenOptions::new().read(true).open(file_name).unwrap(),
         )),
@@ -14,7 +16,7 @@ pub fn get_reader(arg: &str) -> Box<dyn BufRead> {
 pub fn get_writer(arg: &str) -> Box<dyn Write> {
     match arg {
         "-" => Box::new(io::stdout().lock()),
-        x if x.is_empty() => Box::new(io::stdout().lock()),
+        "" => Box::new(io::stdout().lock()),
         file_name => Box::new(
             OpenOptions::new()
                 .write(true)
@@ -25,3 +27,11 @@ pub fn get_writer(arg: &str) -> Box<dyn Write> {
         ),
     }
 }
+
+/// Format named by the extension of the file, None if it names none.
+pub fn format_from_extension<T: FromStr>(file_name: &str) -> Option<T> {
+    Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| T::from_str(extension).ok())
+}
@@ -0,0 +1,124 @@
+use super::{WavError, WavSpec};
+use std::io::Write;
+
+// version of the AIFF-C specification, a timestamp
+const AIFC_VERSION: u32 = 0xA280_5140;
+
+/// Sample rate as the 80 bits extended precision float of the COMM chunk: sign and
+/// 15 bits exponent, then a 64 bits mantissa with an explicit integer bit.
+fn extended(sample_rate: u32) -> [u8; 10] {
+    let mut bytes = [0; 10];
+    if sample_rate == 0 {
+        return bytes;
+    }
+    let shift = (sample_rate as u64).leading_zeros();
+    bytes[..2].copy_from_slice(&(16383 + 63 - shift as u16).to_be_bytes());
+    bytes[2..].copy_from_slice(&((sample_rate as u64) << shift).to_be_bytes());
+    bytes
+}
+
+/// Write samples between -1 and 1 as an AIFF file, big endian with signed integers.
+/// Float samples need the compression type of AIFF-C, they are written as "fl32".
+pub fn write_aiff<T>(samples: T, spec: &WavSpec, writer: &mut dyn Write) -> Result<(), WavError>
+where
+    T: IntoIterator<Item = f32>,
+    T::IntoIter: ExactSizeIterator,
+{
+    let samples = samples.into_iter();
+    let frames = samples.len() / spec.channels as usize;
+    let data_size = spec.data_size(frames as u64);
+    let float = spec.sample_format.is_float();
+
+    let mut comm: Vec<u8> = Vec::with_capacity(40);
+    comm.extend_from_slice(&spec.channels.to_be_bytes()); // number of channels
+    comm.extend_from_slice(&(frames as u32).to_be_bytes()); // sample frames
+    comm.extend_from_slice(&spec.sample_format.bits_per_sample().to_be_bytes()); // sample size
+    comm.extend_from_slice(&extended(spec.sample_rate)); // sample rate
+    if float {
+        comm.extend_from_slice(b"fl32"); // compression type
+        comm.extend_from_slice(b"\x1532-bit floating point"); // compression name, a pascal string
+    }
+
+    // chunks are padded to an even size
+    let form_size = 4
+        + if float { 12 } else { 0 }
+        + (8 + comm.len() as u64)
+        + (8 + 8 + data_size + data_size % 2);
+    if form_size > u32::MAX as u64 {
+        return Err(WavError::TooLarge {
+            container: "AIFF",
+            size: form_size + 8,
+        });
+    }
+
+    let mut header: Vec<u8> = Vec::with_capacity(72);
+    header.extend_from_slice(b"FORM");
+    header.extend_from_slice(&(form_size as u32).to_be_bytes()); // FORM chunk size
+    header.extend_from_slice(if float { b"AIFC" } else { b"AIFF" });
+    if float {
+        header.extend_from_slice(b"FVER");
+        header.extend_from_slice(&4_u32.to_be_bytes()); // FVER chunk size
+        header.extend_from_slice(&AIFC_VERSION.to_be_bytes());
+    }
+    header.extend_from_slice(b"COMM");
+    header.extend_from_slice(&(comm.len() as u32).to_be_bytes()); // COMM chunk size
+    header.extend_from_slice(&comm);
+    header.extend_from_slice(b"SSND");
+    header.extend_from_slice(&(8 + data_size as u32).to_be_bytes()); // SSND chunk size
+    header.extend_from_slice(&0_u32.to_be_bytes()); // offset of the samples
+    header.extend_from_slice(&0_u32.to_be_bytes()); // block size
+    writer.write_all(&header)?;
+
+    spec.sample_format
+        .write_be(samples.take(frames * spec.channels as usize), writer)?;
+    if data_size % 2 == 1 {
+        writer.write_all(&[0])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_aiff() {
+    use super::SampleFormat;
+
+    assert_eq!(extended(44100), [0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(extended(8000), [0x40, 0x0B, 0xFA, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(extended(1), [0x3F, 0xFF, 0x80, 0, 0, 0, 0, 0, 0, 0]);
+
+    let aiff = |sample_format: SampleFormat, data: &[f32]| {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_aiff(
+            data.iter().copied(),
+            &WavSpec::mono(44100, sample_format),
+            &mut bytes,
+        )
+        .unwrap();
+        bytes
+    };
+    let bytes = aiff(SampleFormat::S16, &[1.0, -1.0, 0.0]);
+    assert_eq!(&bytes[..12], b"FORM\0\0\0\x34AIFF");
+    assert_eq!(bytes.len(), 8 + 0x34);
+    assert_eq!(&bytes[12..20], b"COMM\0\0\0\x12");
+    assert_eq!(&bytes[20..28], &[0, 1, 0, 0, 0, 3, 0, 16]);
+    assert_eq!(&bytes[28..38], &extended(44100));
+    assert_eq!(&bytes[38..54], b"SSND\0\0\0\x0e\0\0\0\0\0\0\0\0");
+    assert_eq!(&bytes[54..], &[0x7f, 0xff, 0x80, 0x01, 0, 0]);
+
+    // 8 bit samples are signed, odd sizes padded
+    let bytes = aiff(SampleFormat::U8, &[-1.0]);
+    assert_eq!(&bytes[bytes.len() - 2..], &[0x81, 0]);
+    assert_eq!(bytes.len() % 2, 0);
+
+    let bytes = aiff(SampleFormat::F32, &[0.5]);
+    assert_eq!(&bytes[8..12], b"AIFC");
+    assert_eq!(&bytes[12..24], b"FVER\0\0\0\x04\xA2\x80\x51\x40");
+    assert_eq!(&bytes[24..32], b"COMM\0\0\0\x2c");
+    assert_eq!(&bytes[50..54], b"fl32");
+    assert_eq!(&bytes[bytes.len() - 4..], &0.5_f32.to_be_bytes());
+    assert_eq!(
+        bytes.len(),
+        8 + u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize
+    );
+}
@@ -0,0 +1,76 @@
+use super::{SampleFormat, WavError, WavSpec};
+use std::io::Write;
+
+// header of 24 bytes and the shortest annotation, 4 NUL bytes
+const DATA_OFFSET: u32 = 28;
+
+// data size of a stream whose length isn't known
+const UNKNOWN_SIZE: u32 = u32::MAX;
+
+/// Encoding of the samples in the header of AU files.
+fn encoding(sample_format: SampleFormat) -> u32 {
+    match sample_format {
+        SampleFormat::U8 => 2,
+        SampleFormat::S16 => 3,
+        SampleFormat::S24 => 4,
+        SampleFormat::S32 => 5,
+        SampleFormat::F32 => 6,
+    }
+}
+
+/// Write samples between -1 and 1 as a Sun AU file, big endian like AIFF.
+/// Audio larger than 4 GiB is written with the unknown data size, so that readers
+/// take the samples until the end of the file.
+pub fn write_au<T>(samples: T, spec: &WavSpec, writer: &mut dyn Write) -> Result<(), WavError>
+where
+    T: IntoIterator<Item = f32>,
+    T::IntoIter: ExactSizeIterator,
+{
+    let samples = samples.into_iter();
+    let frames = samples.len() / spec.channels as usize;
+    let data_size = u32::try_from(spec.data_size(frames as u64)).unwrap_or(UNKNOWN_SIZE);
+
+    let mut header: Vec<u8> = Vec::with_capacity(DATA_OFFSET as usize);
+    header.extend_from_slice(b".snd");
+    header.extend_from_slice(&DATA_OFFSET.to_be_bytes()); // offset of the samples
+    header.extend_from_slice(&data_size.to_be_bytes()); // data size
+    header.extend_from_slice(&encoding(spec.sample_format).to_be_bytes()); // encoding
+    header.extend_from_slice(&spec.sample_rate.to_be_bytes()); // sample rate
+    header.extend_from_slice(&(spec.channels as u32).to_be_bytes()); // number of channels
+    header.extend_from_slice(&[0; 4]); // annotation
+    writer.write_all(&header)?;
+
+    spec.sample_format
+        .write_be(samples.take(frames * spec.channels as usize), writer)?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_au() {
+    let au = |spec: WavSpec, data: &[f32]| {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_au(data.iter().copied(), &spec, &mut bytes).unwrap();
+        bytes
+    };
+    let bytes = au(WavSpec::mono(8000, SampleFormat::S16), &[1.0, -1.0]);
+    assert_eq!(&bytes[..4], b".snd");
+    assert_eq!(
+        &bytes[4..28],
+        &[0, 0, 0, 28, 0, 0, 0, 4, 0, 0, 0, 3, 0, 0, 0x1F, 0x40, 0, 0, 0, 1, 0, 0, 0, 0]
+    );
+    assert_eq!(&bytes[28..], &[0x7f, 0xff, 0x80, 0x01]);
+
+    let stereo = WavSpec {
+        channels: 2,
+        sample_rate: 44100,
+        sample_format: SampleFormat::U8,
+    };
+    // the incomplete frame is dropped
+    let bytes = au(stereo, &[1.0, -1.0, 0.0]);
+    assert_eq!(&bytes[8..12], &[0, 0, 0, 2]);
+    assert_eq!(&bytes[12..16], &[0, 0, 0, 2]);
+    assert_eq!(&bytes[20..24], &[0, 0, 0, 2]);
+    assert_eq!(&bytes[28..], &[0x7f, 0x81]);
+}
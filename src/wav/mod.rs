@@ -1,2 +1,18 @@
+mod aiff_writer;
+mod au_writer;
+mod raw_writer;
+mod sample_format;
+mod wav_error;
+mod wav_metadata;
+mod wav_reader;
+mod wav_spec;
 mod wav_writer;
-pub use wav_writer::write_wav;
+pub use aiff_writer::write_aiff;
+pub use au_writer::write_au;
+pub use raw_writer::{write_raw, RawFormat};
+pub use sample_format::SampleFormat;
+pub use wav_error::WavError;
+pub use wav_metadata::{Cue, WavMetadata};
+pub use wav_reader::{read_wav, Wav};
+pub use wav_spec::WavSpec;
+pub use wav_writer::{write_wav, WavWriter};
@@ -0,0 +1,86 @@
+use super::SampleFormat;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Encoding and byte order of headerless PCM samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawFormat {
+    S16Le,
+    S16Be,
+    U8,
+    F32Le,
+}
+
+impl FromStr for RawFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "s16le" => Ok(RawFormat::S16Le),
+            "s16be" => Ok(RawFormat::S16Be),
+            "u8" => Ok(RawFormat::U8),
+            "f32le" => Ok(RawFormat::F32Le),
+            _ => Err(format!("Raw format not found: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for RawFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawFormat::S16Le => write!(f, "s16le"),
+            RawFormat::S16Be => write!(f, "s16be"),
+            RawFormat::U8 => write!(f, "u8"),
+            RawFormat::F32Le => write!(f, "f32le"),
+        }
+    }
+}
+
+impl RawFormat {
+    pub fn sample_format(&self) -> SampleFormat {
+        match self {
+            RawFormat::S16Le | RawFormat::S16Be => SampleFormat::S16,
+            RawFormat::U8 => SampleFormat::U8,
+            RawFormat::F32Le => SampleFormat::F32,
+        }
+    }
+
+    pub fn is_big_endian(&self) -> bool {
+        matches!(self, RawFormat::S16Be)
+    }
+}
+
+/// Write mono samples, between -1 and 1, as raw PCM without any header.
+pub fn write_raw(data: &[f32], format: RawFormat, writer: &mut dyn Write) -> io::Result<()> {
+    let sample_format = format.sample_format();
+    let size = sample_format.bytes_per_sample();
+    let mut bytes: Vec<u8> = Vec::with_capacity(data.len() * size);
+    for sample in data {
+        sample_format.encode_le(*sample, &mut bytes);
+        if format.is_big_endian() {
+            let start = bytes.len() - size;
+            bytes[start..].reverse();
+        }
+    }
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+#[test]
+fn test_write_raw() {
+    let raw = |format: RawFormat| {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_raw(&[1.0, -1.0], format, &mut bytes).unwrap();
+        bytes
+    };
+    assert_eq!(raw(RawFormat::S16Le), vec![0xff, 0x7f, 0x01, 0x80]);
+    assert_eq!(raw(RawFormat::S16Be), vec![0x7f, 0xff, 0x80, 0x01]);
+    assert_eq!(raw(RawFormat::U8), vec![255, 1]);
+    let mut expected = 1_f32.to_le_bytes().to_vec();
+    expected.extend_from_slice(&(-1_f32).to_le_bytes());
+    assert_eq!(raw(RawFormat::F32Le), expected);
+    assert_eq!(
+        RawFormat::from_str("S16BE").map(|format| format.to_string()),
+        Ok(String::from("s16be"))
+    );
+}
@@ -0,0 +1,155 @@
+use crate::polyphonia::MAX_AMPLITUDE;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+// bytes encoded before they are handed to the writer
+pub(crate) const BUFFER_SIZE: usize = 1 << 16;
+
+/// Encoding of the samples written to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    U8,
+    S16,
+    S24,
+    S32,
+    F32,
+}
+
+impl FromStr for SampleFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "u8" => Ok(SampleFormat::U8),
+            "s16" => Ok(SampleFormat::S16),
+            "s24" => Ok(SampleFormat::S24),
+            "s32" => Ok(SampleFormat::S32),
+            "f32" | "float" => Ok(SampleFormat::F32),
+            _ => Err(format!("Sample format not found: {}", s)),
+        }
+    }
+}
+
+impl SampleFormat {
+    pub fn bits_per_sample(&self) -> u16 {
+        match self {
+            SampleFormat::U8 => 8,
+            SampleFormat::S16 => 16,
+            SampleFormat::S24 => 24,
+            SampleFormat::S32 | SampleFormat::F32 => 32,
+        }
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        self.bits_per_sample() as usize / 8
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, SampleFormat::F32)
+    }
+
+    /// Signed integer of a sample between -1 and 1, clipping the samples out of range.
+    /// Float samples are quantized to 32 bits.
+    pub fn quantize(&self, sample: f32) -> i32 {
+        let clipped = sample.clamp(-1.0, 1.0);
+        match self {
+            SampleFormat::U8 => f32::round(clipped * 127.0) as i32,
+            SampleFormat::S16 => f32::round(clipped * MAX_AMPLITUDE) as i32,
+            SampleFormat::S24 => f32::round(clipped * 8_388_607.0) as i32,
+            SampleFormat::S32 | SampleFormat::F32 => {
+                f64::round(clipped as f64 * i32::MAX as f64) as i32
+            }
+        }
+    }
+
+    /// Append the little endian bytes of a sample between -1 and 1, integer formats
+    /// clip the samples out of range.
+    pub fn encode_le(&self, sample: f32, buffer: &mut Vec<u8>) {
+        let value = self.quantize(sample);
+        match self {
+            // 8 bit samples are unsigned
+            SampleFormat::U8 => buffer.push((value + 128) as u8),
+            SampleFormat::S16 => buffer.extend_from_slice(&(value as i16).to_le_bytes()),
+            SampleFormat::S24 => buffer.extend_from_slice(&value.to_le_bytes()[..3]),
+            SampleFormat::S32 => buffer.extend_from_slice(&value.to_le_bytes()),
+            SampleFormat::F32 => buffer.extend_from_slice(&sample.to_le_bytes()),
+        }
+    }
+
+    /// Append the big endian bytes of a sample, as AIFF and AU store them: unlike WAV
+    /// their 8 bit samples are signed.
+    pub fn encode_be(&self, sample: f32, buffer: &mut Vec<u8>) {
+        let start = buffer.len();
+        self.encode_le(sample, buffer);
+        buffer[start..].reverse();
+        if let SampleFormat::U8 = self {
+            buffer[start] ^= 0x80;
+        }
+    }
+
+    /// Write the big endian bytes of the samples as they come, a buffer at a time.
+    pub(crate) fn write_be<T: Iterator<Item = f32>>(
+        &self,
+        samples: T,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let mut buffer: Vec<u8> = Vec::with_capacity(BUFFER_SIZE);
+        for sample in samples {
+            self.encode_be(sample, &mut buffer);
+            if buffer.len() >= BUFFER_SIZE {
+                writer.write_all(&buffer)?;
+                buffer.clear();
+            }
+        }
+        writer.write_all(&buffer)
+    }
+
+    /// Sample encoded in the little endian `bytes`, as long as [Self::bytes_per_sample].
+    pub fn decode_le(&self, bytes: &[u8]) -> f32 {
+        match self {
+            SampleFormat::U8 => (bytes[0] as f32 - 128.0) / 127.0,
+            SampleFormat::S16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / MAX_AMPLITUDE,
+            SampleFormat::S24 => {
+                // the sign is extended shifting the 3 bytes to the top of an i32
+                let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                value as f32 / 8_388_607.0
+            }
+            SampleFormat::S32 => {
+                let value = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                (value as f64 / i32::MAX as f64) as f32
+            }
+            SampleFormat::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+#[test]
+fn test_encode_le() {
+    let encode = |format: SampleFormat, sample: f32| {
+        let mut buffer = Vec::new();
+        format.encode_le(sample, &mut buffer);
+        buffer
+    };
+    assert_eq!(encode(SampleFormat::U8, 0.0), vec![128]);
+    assert_eq!(encode(SampleFormat::U8, -1.0), vec![1]);
+    assert_eq!(encode(SampleFormat::S16, 1.0), vec![0xff, 0x7f]);
+    assert_eq!(encode(SampleFormat::S16, -2.0), vec![0x01, 0x80]);
+    assert_eq!(encode(SampleFormat::S24, -1.0), vec![0x01, 0x00, 0x80]);
+    assert_eq!(encode(SampleFormat::S32, 1.0), vec![0xff, 0xff, 0xff, 0x7f]);
+    assert_eq!(
+        encode(SampleFormat::F32, 0.5),
+        0.5_f32.to_le_bytes().to_vec()
+    );
+    let encode_be = |format: SampleFormat, sample: f32| {
+        let mut buffer = Vec::new();
+        format.encode_be(sample, &mut buffer);
+        buffer
+    };
+    assert_eq!(encode_be(SampleFormat::U8, -1.0), vec![0x81]);
+    assert_eq!(encode_be(SampleFormat::S24, -1.0), vec![0x80, 0x00, 0x01]);
+    assert_eq!(
+        encode_be(SampleFormat::F32, 0.5),
+        0.5_f32.to_be_bytes().to_vec()
+    );
+    assert_eq!(SampleFormat::from_str("S24"), Ok(SampleFormat::S24));
+    assert!(SampleFormat::from_str("s12").is_err());
+}
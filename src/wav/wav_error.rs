@@ -0,0 +1,74 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum WavError {
+    Io(io::Error),
+    NotRiff,
+    NotWave,
+    MissingChunk(&'static str),
+    /// Chunk whose size goes past the end of the file.
+    Truncated(String),
+    MalformedFormat(String),
+    UnsupportedFormat {
+        format_code: u16,
+        bits_per_sample: u16,
+    },
+    /// Sample rate whose byte rate doesn't fit in the 32 bits of the header.
+    UnsupportedSampleRate(u32),
+    /// Audio whose sizes don't fit in the 32 bits of the header of the container.
+    TooLarge {
+        container: &'static str,
+        size: u64,
+    },
+    /// Frames written to a stream that differ from the ones declared in its header.
+    LengthMismatch {
+        declared: u64,
+        written: u64,
+    },
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::Io(err) => write!(f, "Wav I/O error: {}", err),
+            WavError::NotRiff => write!(f, "Not a RIFF file"),
+            WavError::NotWave => write!(f, "RIFF file doesn't contain WAVE audio"),
+            WavError::MissingChunk(id) => write!(f, "Missing {:?} chunk", id),
+            WavError::Truncated(id) => write!(f, "Chunk {:?} is truncated", id),
+            WavError::MalformedFormat(reason) => write!(f, "Malformed fmt chunk: {}", reason),
+            WavError::UnsupportedFormat {
+                format_code,
+                bits_per_sample,
+            } => write!(
+                f,
+                "Unsupported format {:#06x} with {} bits per sample",
+                format_code, bits_per_sample
+            ),
+            WavError::UnsupportedSampleRate(sample_rate) => write!(
+                f,
+                "Wav can't store a sample rate of {} Hz in its header",
+                sample_rate
+            ),
+            WavError::TooLarge { container, size } => write!(
+                f,
+                "Audio of {} bytes is too large for a {} file",
+                size, container
+            ),
+            WavError::LengthMismatch { declared, written } => write!(
+                f,
+                "Wav declared {} frames but {} were written",
+                declared, written
+            ),
+        }
+    }
+}
+
+impl Error for WavError {}
+
+impl From<io::Error> for WavError {
+    fn from(err: io::Error) -> Self {
+        WavError::Io(err)
+    }
+}
@@ -0,0 +1,190 @@
+use super::wav_reader::u32_at;
+
+/// Label of a position of the audio, shown by audio editors along the waveform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub frame: u32,
+    pub label: String,
+}
+
+/// Description of the audio: the LIST/INFO chunk and the cue points with their labels.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WavMetadata {
+    pub title: Option<String>,
+    pub comment: Option<String>,
+    pub software: Option<String>,
+    pub cues: Vec<Cue>,
+}
+
+/// Chunk with its size and the padding of odd sizes.
+fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut chunk: Vec<u8> = Vec::with_capacity(body.len() + 9);
+    chunk.extend_from_slice(id);
+    chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+/// Text of a chunk, terminated by a NUL.
+fn text_chunk(id: &[u8; 4], text: &str) -> Vec<u8> {
+    chunk(id, &[text.as_bytes(), &[0]].concat())
+}
+
+fn text(body: &[u8]) -> String {
+    let end = body
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(body.len());
+    String::from_utf8_lossy(&body[..end]).into_owned()
+}
+
+/// Sub chunks of a LIST chunk after its type, a truncated one ends the list.
+fn sub_chunks(mut bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = Vec::new();
+    while bytes.len() >= 8 {
+        let size = u32_at(bytes, 4) as usize;
+        if size > bytes.len() - 8 {
+            break;
+        }
+        chunks.push((&bytes[..4], &bytes[8..8 + size]));
+        bytes = &bytes[usize::min(8 + size + size % 2, bytes.len())..];
+    }
+    chunks
+}
+
+impl WavMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == WavMetadata::default()
+    }
+
+    /// LIST/INFO, cue and LIST/adtl chunks, the labels of the cues are numbered from 1.
+    pub fn chunks(&self) -> Vec<u8> {
+        let mut chunks: Vec<u8> = Vec::new();
+
+        let mut info = b"INFO".to_vec();
+        for (id, text) in [
+            (b"INAM", &self.title),
+            (b"ICMT", &self.comment),
+            (b"ISFT", &self.software),
+        ] {
+            if let Some(text) = text {
+                info.extend(text_chunk(id, text));
+            }
+        }
+        if info.len() > 4 {
+            chunks.extend(chunk(b"LIST", &info));
+        }
+
+        if self.cues.is_empty() {
+            return chunks;
+        }
+        let mut cue_points = (self.cues.len() as u32).to_le_bytes().to_vec();
+        let mut labels = b"adtl".to_vec();
+        for (id, cue) in (1_u32..).zip(self.cues.iter()) {
+            cue_points.extend_from_slice(&id.to_le_bytes()); // cue id
+            cue_points.extend_from_slice(&cue.frame.to_le_bytes()); // play order position
+            cue_points.extend_from_slice(b"data"); // chunk of the cue
+            cue_points.extend_from_slice(&0_u32.to_le_bytes()); // chunk start
+            cue_points.extend_from_slice(&0_u32.to_le_bytes()); // block start
+            cue_points.extend_from_slice(&cue.frame.to_le_bytes()); // sample offset
+
+            let mut label = id.to_le_bytes().to_vec();
+            label.extend_from_slice(cue.label.as_bytes());
+            label.push(0);
+            labels.extend(chunk(b"labl", &label));
+        }
+        chunks.extend(chunk(b"cue ", &cue_points));
+        chunks.extend(chunk(b"LIST", &labels));
+        chunks
+    }
+
+    /// Read the metadata from the chunks of a file, ignoring the ones it doesn't know.
+    pub fn from_chunks<'a, T: IntoIterator<Item = (&'a str, &'a [u8])>>(chunks: T) -> Self {
+        let mut metadata = WavMetadata::default();
+        let mut cue_frames: Vec<(u32, u32)> = Vec::new();
+        let mut labels: Vec<(u32, String)> = Vec::new();
+        for (id, body) in chunks {
+            match id {
+                "LIST" if body.starts_with(b"INFO") => {
+                    for (id, body) in sub_chunks(&body[4..]) {
+                        match id {
+                            b"INAM" => metadata.title = Some(text(body)),
+                            b"ICMT" => metadata.comment = Some(text(body)),
+                            b"ISFT" => metadata.software = Some(text(body)),
+                            _ => {}
+                        }
+                    }
+                }
+                "LIST" if body.starts_with(b"adtl") => {
+                    labels.extend(
+                        sub_chunks(&body[4..])
+                            .into_iter()
+                            .filter(|(id, body)| *id == b"labl" && body.len() >= 4)
+                            .map(|(_, body)| (u32_at(body, 0), text(&body[4..]))),
+                    );
+                }
+                "cue " if body.len() >= 4 => {
+                    cue_frames.extend(
+                        body[4..]
+                            .chunks_exact(24)
+                            .map(|point| (u32_at(point, 0), u32_at(point, 20))),
+                    );
+                }
+                _ => {}
+            }
+        }
+        metadata.cues = cue_frames
+            .into_iter()
+            .map(|(id, frame)| Cue {
+                frame,
+                label: labels
+                    .iter()
+                    .find(|(label_id, _)| *label_id == id)
+                    .map(|(_, label)| label.clone())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        metadata
+    }
+}
+
+#[test]
+fn test_metadata_chunks() {
+    let metadata = WavMetadata {
+        title: Some(String::from("sos")),
+        comment: Some(String::from("sos")),
+        software: Some(String::from("morse_traducer")),
+        cues: vec![
+            Cue {
+                frame: 0,
+                label: String::from("s"),
+            },
+            Cue {
+                frame: 4410,
+                label: String::from("o"),
+            },
+        ],
+    };
+    let chunks = metadata.chunks();
+    assert_eq!(chunks.len() % 2, 0);
+    assert_eq!(&chunks[..4], b"LIST");
+    assert_eq!(&chunks[8..12], b"INFO");
+    assert_eq!(&chunks[12..24], b"INAM\x04\0\0\0sos\0");
+
+    let parsed = sub_chunks(&chunks)
+        .into_iter()
+        .map(|(id, body)| (std::str::from_utf8(id).unwrap(), body))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        parsed.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec!["LIST", "cue ", "LIST"]
+    );
+    // cue points are 24 bytes, labels are padded to an even size
+    assert_eq!(parsed[1].1.len(), 4 + 2 * 24);
+    assert_eq!(&parsed[2].1[4..18], b"labl\x06\0\0\0\x01\0\0\0s\0");
+    assert_eq!(WavMetadata::from_chunks(parsed), metadata);
+    assert!(WavMetadata::default().chunks().is_empty());
+}
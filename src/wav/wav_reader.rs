@@ -0,0 +1,365 @@
+use super::{SampleFormat, WavError, WavMetadata, WavSpec};
+use std::io::Read;
+
+use super::wav_spec::{FORMAT_EXTENSIBLE, FORMAT_IEEE_FLOAT, FORMAT_PCM};
+
+/// Audio read from a WAV file, with the samples of the channels interleaved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wav {
+    pub spec: WavSpec,
+    pub metadata: WavMetadata,
+    /// Samples between -1 and 1, the ones of every channel for the first frame,
+    /// then for the second and so on.
+    pub samples: Vec<f32>,
+}
+
+impl Wav {
+    /// Number of samples of every channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.spec.channels as usize
+    }
+
+    pub fn channel(&self, channel: u16) -> Vec<f32> {
+        self.samples
+            .iter()
+            .skip(channel as usize)
+            .step_by(self.spec.channels as usize)
+            .copied()
+            .collect()
+    }
+}
+
+pub(crate) fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+pub(crate) fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+pub(crate) fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u32_at(bytes, offset) as u64 | (u32_at(bytes, offset + 4) as u64) << 32
+}
+
+/// Chunks of a RIFF file, identifier and content, skipping the padding byte of odd sizes.
+///
+/// The size of the data of RF64 files is the one of the ds64 chunk.
+fn chunks(mut bytes: &[u8], ds64_data_size: Option<u64>) -> Result<Vec<(String, &[u8])>, WavError> {
+    let mut chunks = Vec::new();
+    while bytes.len() >= 8 {
+        let id = String::from_utf8_lossy(&bytes[..4]).into_owned();
+        let size = u32_at(bytes, 4) as u64;
+        let body = &bytes[8..];
+        let size = match (size, ds64_data_size) {
+            (0xFFFF_FFFF, Some(data_size)) if id == "data" => data_size,
+            // streamed data of unknown length lasts until the end of the file
+            (0xFFFF_FFFF, None) if id == "data" => body.len() as u64,
+            (size, _) => size,
+        };
+        if size > body.len() as u64 {
+            return Err(WavError::Truncated(id));
+        }
+        let size = size as usize;
+        chunks.push((id, &body[..size]));
+        bytes = &body[usize::min(size + size % 2, body.len())..];
+    }
+    Ok(chunks)
+}
+
+fn sample_format(fmt: &[u8]) -> Result<SampleFormat, WavError> {
+    let mut format_code = u16_at(fmt, 0);
+    let bits_per_sample = u16_at(fmt, 14);
+    if format_code == FORMAT_EXTENSIBLE {
+        // the actual format is at the start of the sub format GUID
+        if fmt.len() < 40 {
+            return Err(WavError::MalformedFormat(String::from(
+                "extensible format without sub format",
+            )));
+        }
+        format_code = u16_at(fmt, 24);
+    }
+    match (format_code, bits_per_sample) {
+        (FORMAT_PCM, 8) => Ok(SampleFormat::U8),
+        (FORMAT_PCM, 16) => Ok(SampleFormat::S16),
+        (FORMAT_PCM, 24) => Ok(SampleFormat::S24),
+        (FORMAT_PCM, 32) => Ok(SampleFormat::S32),
+        (FORMAT_IEEE_FLOAT, 32) => Ok(SampleFormat::F32),
+        _ => Err(WavError::UnsupportedFormat {
+            format_code,
+            bits_per_sample,
+        }),
+    }
+}
+
+/// Read a RIFF/WAVE file, or its RF64 and BW64 extensions, skipping the chunks
+/// other than the format and the data.
+pub fn read_wav(reader: &mut dyn Read) -> Result<Wav, WavError> {
+    let mut bytes: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() < 12 || !matches!(&bytes[..4], b"RIFF" | b"RF64" | b"BW64") {
+        return Err(WavError::NotRiff);
+    }
+    if &bytes[8..12] != b"WAVE" {
+        return Err(WavError::NotWave);
+    }
+
+    // 64 bits sizes are in the ds64 chunk, that comes first
+    let ds64_data_size = match &bytes[..4] {
+        b"RIFF" => None,
+        _ if bytes.len() >= 36 && &bytes[12..16] == b"ds64" => Some(u64_at(&bytes, 28)),
+        _ => return Err(WavError::MissingChunk("ds64")),
+    };
+    let chunks = chunks(&bytes[12..], ds64_data_size)?;
+    let find = |id: &'static str| {
+        chunks
+            .iter()
+            .find(|(chunk_id, _)| chunk_id == id)
+            .map(|(_, body)| *body)
+            .ok_or(WavError::MissingChunk(id))
+    };
+    let fmt = find("fmt ")?;
+    if fmt.len() < 16 {
+        return Err(WavError::MalformedFormat(format!(
+            "{} bytes long instead of at least 16",
+            fmt.len()
+        )));
+    }
+    let channels = u16_at(fmt, 2);
+    let sample_rate = u32_at(fmt, 4);
+    let block_align = u16_at(fmt, 12) as usize;
+    let sample_format = sample_format(fmt)?;
+    if channels == 0 {
+        return Err(WavError::MalformedFormat(String::from("no channels")));
+    }
+    if block_align != channels as usize * sample_format.bytes_per_sample() {
+        return Err(WavError::MalformedFormat(format!(
+            "block size {} doesn't fit {} channels of {} bits",
+            block_align,
+            channels,
+            sample_format.bits_per_sample()
+        )));
+    }
+
+    let data = find("data")?;
+    let samples = data
+        .chunks_exact(block_align)
+        .flat_map(|frame| frame.chunks_exact(sample_format.bytes_per_sample()))
+        .map(|sample| sample_format.decode_le(sample))
+        .collect();
+    Ok(Wav {
+        spec: WavSpec {
+            channels,
+            sample_rate,
+            sample_format,
+        },
+        metadata: WavMetadata::from_chunks(chunks.iter().map(|(id, body)| (id.as_str(), *body))),
+        samples,
+    })
+}
+
+#[test]
+fn test_round_trip() {
+    use super::write_wav;
+
+    let samples = (0..1000)
+        .map(|i| f32::sin(i as f32 * 0.05) * 0.9)
+        .chain([1.0, -1.0, 0.0])
+        .collect::<Vec<f32>>();
+    for format in [
+        SampleFormat::U8,
+        SampleFormat::S16,
+        SampleFormat::S24,
+        SampleFormat::S32,
+        SampleFormat::F32,
+    ] {
+        let mut bytes: Vec<u8> = Vec::new();
+        let spec = WavSpec::mono(16000, format);
+        write_wav(&samples, &spec, &mut bytes).unwrap();
+        let wav = read_wav(&mut bytes.as_slice()).unwrap();
+        assert_eq!(wav.spec, spec);
+        assert_eq!(wav.frames(), samples.len());
+
+        // samples are the same, up to the quantization of the format
+        let step = match format {
+            SampleFormat::F32 => 0.0,
+            format => 1.0 / ((1_u64 << (format.bits_per_sample() - 1)) - 1) as f32,
+        };
+        for (read, written) in wav.samples.iter().zip(samples.iter()) {
+            assert!((read - written).abs() <= step / 2.0 + 1e-6);
+        }
+        // and written again they give the same file
+        let mut rewritten: Vec<u8> = Vec::new();
+        write_wav(&wav.samples, &wav.spec, &mut rewritten).unwrap();
+        assert_eq!(rewritten, bytes);
+    }
+}
+
+#[test]
+fn test_read_chunks() {
+    let mut fmt = vec![1, 0, 2, 0]; // PCM, stereo
+    fmt.extend_from_slice(&8000_u32.to_le_bytes());
+    fmt.extend_from_slice(&32000_u32.to_le_bytes());
+    fmt.extend_from_slice(&[4, 0, 16, 0]);
+    let frames: [i16; 4] = [i16::MAX, 0, -i16::MAX, i16::MAX];
+    let data = frames
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes())
+        .collect::<Vec<u8>>();
+
+    let mut body = b"WAVE".to_vec();
+    // unknown chunk with an odd size, followed by its padding
+    body.extend_from_slice(b"junk");
+    body.extend_from_slice(&3_u32.to_le_bytes());
+    body.extend_from_slice(&[1, 2, 3, 0]);
+    body.extend_from_slice(b"fmt ");
+    body.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+    body.extend_from_slice(&fmt);
+    body.extend_from_slice(b"data");
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&data);
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&body);
+
+    let wav = read_wav(&mut bytes.as_slice()).unwrap();
+    assert_eq!(wav.spec.channels, 2);
+    assert_eq!(wav.spec.sample_rate, 8000);
+    assert_eq!(wav.frames(), 2);
+    assert_eq!(wav.channel(0), vec![1.0, -1.0]);
+    assert_eq!(wav.channel(1), vec![0.0, 1.0]);
+
+    // typed errors for malformed files
+    assert!(matches!(
+        read_wav(&mut &bytes[..bytes.len() - 1]),
+        Err(WavError::Truncated(id)) if id == "data"
+    ));
+    assert!(matches!(
+        read_wav(&mut &b"RIFX\0\0\0\0WAVE"[..]),
+        Err(WavError::NotRiff)
+    ));
+    assert!(matches!(
+        read_wav(&mut &bytes[..36]),
+        Err(WavError::Truncated(id)) if id == "fmt "
+    ));
+    let without_data = {
+        let mut without_data = bytes[..bytes.len() - data.len() - 8].to_vec();
+        let riff_size = without_data.len() as u32 - 8;
+        without_data[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        without_data
+    };
+    assert!(matches!(
+        read_wav(&mut without_data.as_slice()),
+        Err(WavError::MissingChunk("data"))
+    ));
+    let mut float_64 = bytes.clone();
+    float_64[32] = 3; // IEEE float
+    float_64[46] = 64; // bits per sample
+    assert!(matches!(
+        read_wav(&mut float_64.as_slice()),
+        Err(WavError::UnsupportedFormat {
+            format_code: 3,
+            bits_per_sample: 64
+        })
+    ));
+}
+
+#[test]
+fn test_read_rf64() {
+    use super::WavWriter;
+    use std::collections::BTreeMap;
+    use std::io::{self, Seek, SeekFrom, Write};
+
+    // small files can be RF64 too
+    let spec = WavSpec::mono(8000, SampleFormat::S16);
+    let samples = [0.5, -0.5, 0.25];
+    let mut bytes = spec.rf64_header(samples.len() as u64);
+    for sample in samples {
+        spec.sample_format.encode_le(sample, &mut bytes);
+    }
+    // a chunk after the data, that RF64 readers can find only through the ds64 sizes
+    bytes.extend_from_slice(b"LIST\0\0\0\0");
+    let wav = read_wav(&mut bytes.as_slice()).unwrap();
+    assert_eq!(wav.spec, spec);
+    assert_eq!(wav.frames(), 3);
+    assert!((wav.samples[2] - 0.25).abs() < 1e-4);
+
+    // file that keeps only the bytes written, the holes left by seeks read as zeros
+    #[derive(Default)]
+    struct SparseFile {
+        position: u64,
+        length: u64,
+        extents: BTreeMap<u64, Vec<u8>>,
+    }
+    impl Write for SparseFile {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.extents.insert(self.position, buf.to_vec());
+            self.position += buf.len() as u64;
+            self.length = self.length.max(self.position);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Seek for SparseFile {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.position = match pos {
+                SeekFrom::Start(offset) => offset,
+                SeekFrom::Current(offset) => self.position.checked_add_signed(offset).unwrap(),
+                SeekFrom::End(offset) => self.length.checked_add_signed(offset).unwrap(),
+            };
+            Ok(self.position)
+        }
+    }
+    impl SparseFile {
+        fn read_at(&self, offset: u64, length: usize) -> Vec<u8> {
+            let mut bytes = vec![0; length];
+            for (start, extent) in self.extents.iter() {
+                for (i, byte) in extent.iter().enumerate() {
+                    let position = start + i as u64;
+                    if position >= offset && position < offset + length as u64 {
+                        bytes[(position - offset) as usize] = *byte;
+                    }
+                }
+            }
+            bytes
+        }
+    }
+
+    // more than 4 GiB of 16 bits audio switch the header to RF64
+    let frames = (1_u64 << 31) + 1000;
+    let mut file = SparseFile::default();
+    let mut writer = WavWriter::new(&mut file, spec).unwrap();
+    writer.write_samples(samples).unwrap();
+    writer.write_silence(frames - 6).unwrap();
+    writer.write_samples(samples).unwrap();
+    writer.finalize().unwrap();
+
+    let header_size = spec.rf64_header(frames).len();
+    let data_size = 2 * frames;
+    assert_eq!(file.length, header_size as u64 + data_size);
+    let header = file.read_at(0, header_size);
+    assert_eq!(header, spec.rf64_header(frames));
+    assert_eq!(u64_at(&header, 28), data_size);
+
+    // the beginning and the end of the data, read back through the ds64 sizes
+    let mut ends = header;
+    ends[28..36].copy_from_slice(&12_u64.to_le_bytes());
+    ends.extend_from_slice(&file.read_at(header_size as u64, 6));
+    ends.extend_from_slice(&file.read_at(header_size as u64 + data_size - 6, 6));
+    let wav = read_wav(&mut ends.as_slice()).unwrap();
+    assert_eq!(wav.frames(), 6);
+    for (read, written) in wav.samples.iter().zip(samples.iter().cycle()) {
+        assert!((read - written).abs() < 1e-4);
+    }
+    ends[28..36].copy_from_slice(&data_size.to_le_bytes());
+    assert!(matches!(
+        read_wav(&mut ends.as_slice()),
+        Err(WavError::Truncated(id)) if id == "data"
+    ));
+}
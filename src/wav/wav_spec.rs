@@ -0,0 +1,280 @@
+use super::{SampleFormat, WavError};
+
+pub(crate) const FORMAT_PCM: u16 = 1;
+pub(crate) const FORMAT_IEEE_FLOAT: u16 = 3;
+pub(crate) const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+// RIFF, data and frames 64 bits sizes, and the length of the table of other sizes
+const DS64_SIZE: u64 = 28;
+
+// tail of the GUIDs of the sub formats, after the format code
+const SUB_FORMAT_GUID: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// Layout of the samples of a WAV file, from which every field of the header follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavSpec {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub sample_format: SampleFormat,
+}
+
+impl WavSpec {
+    pub fn mono(sample_rate: u32, sample_format: SampleFormat) -> Self {
+        WavSpec {
+            channels: 1,
+            sample_rate,
+            sample_format,
+        }
+    }
+
+    /// Bytes of a frame, one sample for every channel.
+    pub fn block_align(&self) -> u16 {
+        self.channels * self.sample_format.bytes_per_sample() as u16
+    }
+
+    /// Bytes of a second of audio, the spec must have passed [Self::check].
+    pub fn byte_rate(&self) -> u32 {
+        self.sample_rate * self.block_align() as u32
+    }
+
+    /// Check that the byte rate fits in the 32 bits of the header.
+    pub fn check(&self) -> Result<(), WavError> {
+        match self.sample_rate.checked_mul(self.block_align() as u32) {
+            Some(_) => Ok(()),
+            None => Err(WavError::UnsupportedSampleRate(self.sample_rate)),
+        }
+    }
+
+    pub fn format_code(&self) -> u16 {
+        match self.sample_format.is_float() {
+            true => FORMAT_IEEE_FLOAT,
+            false => FORMAT_PCM,
+        }
+    }
+
+    /// More than two channels or more than 16 bits can't be described by the plain
+    /// format, they need WAVE_FORMAT_EXTENSIBLE.
+    pub fn is_extensible(&self) -> bool {
+        self.channels > 2 || self.sample_format.bits_per_sample() > 16
+    }
+
+    /// Speakers of the channels: front center for mono, then front left, front right
+    /// and the following speakers in their standard order.
+    pub fn channel_mask(&self) -> u32 {
+        match self.channels {
+            1 => 0x4,
+            channels if channels <= 18 => (1 << channels) - 1,
+            _ => 0,
+        }
+    }
+
+    /// Content of the fmt chunk.
+    pub fn fmt_chunk(&self) -> Vec<u8> {
+        let mut fmt: Vec<u8> = Vec::with_capacity(40);
+        let format_code = match self.is_extensible() {
+            true => FORMAT_EXTENSIBLE,
+            false => self.format_code(),
+        };
+        fmt.extend_from_slice(&format_code.to_le_bytes()); // format code
+        fmt.extend_from_slice(&self.channels.to_le_bytes()); // number of channels
+        fmt.extend_from_slice(&self.sample_rate.to_le_bytes()); // sample rate
+        fmt.extend_from_slice(&self.byte_rate().to_le_bytes()); // data rate
+        fmt.extend_from_slice(&self.block_align().to_le_bytes()); // block size
+        let bits_per_sample = self.sample_format.bits_per_sample();
+        fmt.extend_from_slice(&bits_per_sample.to_le_bytes()); // bits per sample
+        if self.is_extensible() {
+            fmt.extend_from_slice(&22_u16.to_le_bytes()); // extension size
+            fmt.extend_from_slice(&bits_per_sample.to_le_bytes()); // valid bits per sample
+            fmt.extend_from_slice(&self.channel_mask().to_le_bytes()); // speakers
+            fmt.extend_from_slice(&self.format_code().to_le_bytes()); // sub format
+            fmt.extend_from_slice(&SUB_FORMAT_GUID);
+        } else if self.sample_format.is_float() {
+            fmt.extend_from_slice(&0_u16.to_le_bytes()); // extension size
+        }
+        fmt
+    }
+
+    /// Bytes of the samples of `frames` frames, without the padding of the chunk.
+    pub fn data_size(&self, frames: u64) -> u64 {
+        frames * self.block_align() as u64
+    }
+
+    /// Header of a file with `frames` frames, up to the size of the data chunk included.
+    ///
+    /// Files larger than the 4 GiB that RIFF sizes can address are written as RF64.
+    pub fn header(&self, frames: u64) -> Vec<u8> {
+        self.header_with_chunks(frames, 0)
+    }
+
+    /// Header of a file whose data is followed by `chunks_size` bytes of other chunks.
+    pub fn header_with_chunks(&self, frames: u64, chunks_size: u64) -> Vec<u8> {
+        let rf64 = self.exceeds_riff(frames, false, chunks_size);
+        self.build_header(frames, false, rf64, chunks_size)
+    }
+
+    /// Header with room for the 64 bits sizes of RF64, so that it can be rewritten
+    /// in place once the length of the data is known: files fitting in RIFF keep the
+    /// room as a JUNK chunk.
+    pub fn patchable_header(&self, frames: u64) -> Vec<u8> {
+        self.build_header(frames, true, self.exceeds_riff(frames, true, 0), 0)
+    }
+
+    /// RF64 header, whatever the size of the file.
+    pub fn rf64_header(&self, frames: u64) -> Vec<u8> {
+        self.build_header(frames, true, true, 0)
+    }
+
+    fn riff_size(&self, frames: u64, ds64: bool, chunks_size: u64) -> u64 {
+        let data_size = self.data_size(frames);
+        // formats other than PCM need a fact chunk with the number of frames
+        let fact_size = match self.sample_format.is_float() {
+            true => 12,
+            false => 0,
+        };
+        let ds64_size = match ds64 {
+            true => 8 + DS64_SIZE,
+            false => 0,
+        };
+        // chunks are padded to an even size
+        4 + ds64_size
+            + (8 + self.fmt_chunk().len() as u64)
+            + fact_size
+            + 8
+            + data_size
+            + data_size % 2
+            + chunks_size
+    }
+
+    fn exceeds_riff(&self, frames: u64, ds64: bool, chunks_size: u64) -> bool {
+        self.riff_size(frames, ds64, chunks_size) > u32::MAX as u64
+    }
+
+    fn build_header(&self, frames: u64, ds64: bool, rf64: bool, chunks_size: u64) -> Vec<u8> {
+        let fmt = self.fmt_chunk();
+        let data_size = self.data_size(frames);
+        let riff_size = self.riff_size(frames, ds64 || rf64, chunks_size);
+        // sizes of RF64 are in the ds64 chunk, the 32 bits ones are all 0xFFFFFFFF
+        let size_32 = |size: u64| match rf64 {
+            true => u32::MAX,
+            false => size as u32,
+        };
+
+        let mut header: Vec<u8> = Vec::with_capacity(100);
+        header.extend_from_slice(if rf64 { b"RF64" } else { b"RIFF" });
+        header.extend_from_slice(&size_32(riff_size).to_le_bytes()); // WAVE chunk size
+
+        // WAVE chunk
+        header.extend_from_slice(b"WAVE");
+
+        if ds64 || rf64 {
+            header.extend_from_slice(if rf64 { b"ds64" } else { b"JUNK" });
+            header.extend_from_slice(&(DS64_SIZE as u32).to_le_bytes()); // ds64 chunk size
+            let sizes: [u64; 3] = match rf64 {
+                true => [riff_size, data_size, frames],
+                false => [0; 3],
+            };
+            for size in sizes {
+                header.extend_from_slice(&size.to_le_bytes()); // RIFF, data and frames
+            }
+            header.extend_from_slice(&0_u32.to_le_bytes()); // sizes of other chunks
+        }
+
+        // fmt chunk
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&(fmt.len() as u32).to_le_bytes()); // fmt chunk size
+        header.extend_from_slice(&fmt);
+
+        if self.sample_format.is_float() {
+            header.extend_from_slice(b"fact");
+            header.extend_from_slice(&4_u32.to_le_bytes()); // fact chunk size
+            header.extend_from_slice(&size_32(frames).to_le_bytes()); // sample frames
+        }
+
+        // data chunk
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&size_32(data_size).to_le_bytes()); // data chunk size
+        header
+    }
+
+    /// Header of a stream whose length isn't known when it starts, every size is
+    /// 0xFFFFFFFF and readers take the data until the end of the file.
+    pub fn unknown_length_header(&self) -> Vec<u8> {
+        let mut header = self.header(0);
+        let length = header.len();
+        header[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        if self.sample_format.is_float() {
+            header[length - 12..length - 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+        header[length - 4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        header
+    }
+}
+
+#[test]
+fn test_spec_header() {
+    use super::wav_reader::{u16_at, u32_at, u64_at};
+
+    let stereo = WavSpec {
+        channels: 2,
+        sample_rate: 44100,
+        sample_format: SampleFormat::S16,
+    };
+    let header = stereo.header(10);
+    assert_eq!(u16_at(&header, 20), FORMAT_PCM);
+    assert_eq!(u32_at(&header, 28), 44100 * 4);
+    assert_eq!(u16_at(&header, 32), 4);
+    assert_eq!(header.len(), 44);
+    assert!(stereo.check().is_ok());
+    assert!(WavSpec::mono(u32::MAX / 2, SampleFormat::S24)
+        .check()
+        .is_err());
+
+    // more than 16 bits or 2 channels are extensible, with the format in the sub format
+    for spec in [
+        WavSpec::mono(48000, SampleFormat::S24),
+        WavSpec::mono(48000, SampleFormat::F32),
+        WavSpec {
+            channels: 6,
+            ..stereo
+        },
+    ] {
+        let header = spec.header(10);
+        assert_eq!(u32_at(&header, 16), 40);
+        assert_eq!(u16_at(&header, 20), FORMAT_EXTENSIBLE);
+        assert_eq!(u32_at(&header, 28), spec.byte_rate());
+        assert_eq!(u16_at(&header, 32), spec.block_align());
+        assert_eq!(u16_at(&header, 38), spec.sample_format.bits_per_sample());
+        assert_eq!(u32_at(&header, 40), spec.channel_mask());
+        assert_eq!(u16_at(&header, 44), spec.format_code());
+        assert_eq!(&header[46..60], &SUB_FORMAT_GUID);
+    }
+    assert_eq!(u16_at(&stereo.fmt_chunk(), 0), FORMAT_PCM);
+    assert_eq!(
+        WavSpec {
+            channels: 6,
+            ..stereo
+        }
+        .channel_mask(),
+        0x3F
+    );
+
+    // sizes of RIFF are 32 bits, larger files switch to RF64
+    let frames = (u32::MAX as u64 - 36) / 4;
+    assert_eq!(&stereo.header(frames)[..4], b"RIFF");
+    let header = stereo.header(frames + 1);
+    assert_eq!(&header[..4], b"RF64");
+    assert_eq!(u32_at(&header, 4), u32::MAX);
+    assert_eq!(&header[12..16], b"ds64");
+    assert_eq!(u64_at(&header, 20), 36 + 36 + 4 * (frames + 1));
+    assert_eq!(u64_at(&header, 28), 4 * (frames + 1));
+    assert_eq!(u64_at(&header, 36), frames + 1);
+    assert_eq!(u32_at(&header, header.len() - 4), u32::MAX);
+
+    // the patchable header has the same length, whatever the size
+    let reserved = stereo.patchable_header(0);
+    assert_eq!(&reserved[12..16], b"JUNK");
+    assert_eq!(reserved.len(), stereo.patchable_header(frames * 2).len());
+    assert_eq!(&stereo.patchable_header(frames)[..4], b"RF64");
+}
@@ -1,54 +1,211 @@
-use std::io::{self, Write};
-
-fn make_bytes<T>(number: T) -> Vec<u8>
-where
-    T: Into<u32>,
-{
-    let number: u32 = number.into();
-    let mut b: Vec<u8> = Vec::new();
-    for i in 0..std::mem::size_of::<T>() {
-        b.push(((number >> (8 * i)) & 0xff) as u8);
-    }
-    b
+use super::sample_format::BUFFER_SIZE;
+use super::{WavError, WavMetadata, WavSpec};
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Write samples, between -1 and 1, as a WAV file laid out as `spec`,
+/// or as RF64 if they don't fit in 4 GiB.
+///
+/// Samples of multiple channels are interleaved, a trailing incomplete frame is dropped.
+pub fn write_wav(data: &[f32], spec: &WavSpec, writer: &mut dyn Write) -> Result<(), WavError> {
+    spec.check()?;
+    let frames = data.len() / spec.channels as usize;
+    let data_size = spec.data_size(frames as u64) as usize;
+    writer.write_all(&spec.header(frames as u64))?;
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(data_size + 1);
+    for sample in &data[..frames * spec.channels as usize] {
+        spec.sample_format.encode_le(*sample, &mut bytes);
+    }
+    // chunks are padded to an even size
+    if data_size % 2 == 1 {
+        bytes.push(0);
+    }
+    writer.write_all(&bytes)?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// How the sizes in the header of a stream are written.
+enum Length<W> {
+    /// Placeholder header, rewritten with the actual sizes at the end.
+    Patched(fn(&mut W, SeekFrom) -> io::Result<u64>),
+    Declared(u64),
+    Unknown,
+}
+
+/// Writer of a WAV file whose samples are written one at a time, instead of
+/// being known all before the header.
+///
+/// The sizes of the header are written by [Self::finalize], or when the writer is
+/// dropped ignoring the errors.
+pub struct WavWriter<W: Write> {
+    writer: W,
+    spec: WavSpec,
+    length: Length<W>,
+    buffer: Vec<u8>,
+    samples: u64,
+    // chunks written after the data
+    trailer: Vec<u8>,
+    finalized: bool,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    /// Stream to a seekable writer, its header is patched when the stream ends
+    /// and becomes RF64 if the data passes 4 GiB.
+    pub fn new(mut writer: W, spec: WavSpec) -> Result<Self, WavError> {
+        spec.check()?;
+        writer.write_all(&spec.patchable_header(0))?;
+        Ok(Self::with_length(writer, spec, Length::Patched(W::seek)))
+    }
+}
+
+impl<W: Write> WavWriter<W> {
+    /// Stream `frames` frames to a writer that can't seek, like stdout.
+    pub fn with_known_length(writer: W, spec: WavSpec, frames: u64) -> Result<Self, WavError> {
+        Self::with_metadata(writer, spec, frames, &WavMetadata::default())
+    }
+
+    /// Stream `frames` frames to a writer that can't seek, followed by the metadata.
+    pub fn with_metadata(
+        mut writer: W,
+        spec: WavSpec,
+        frames: u64,
+        metadata: &WavMetadata,
+    ) -> Result<Self, WavError> {
+        spec.check()?;
+        let trailer = metadata.chunks();
+        writer.write_all(&spec.header_with_chunks(frames, trailer.len() as u64))?;
+        let mut wav_writer = Self::with_length(writer, spec, Length::Declared(frames));
+        wav_writer.trailer = trailer;
+        Ok(wav_writer)
+    }
+
+    /// Stream to a writer that can't seek, marking every size as unknown.
+    pub fn with_unknown_length(mut writer: W, spec: WavSpec) -> Result<Self, WavError> {
+        spec.check()?;
+        writer.write_all(&spec.unknown_length_header())?;
+        Ok(Self::with_length(writer, spec, Length::Unknown))
+    }
+
+    fn with_length(writer: W, spec: WavSpec, length: Length<W>) -> Self {
+        WavWriter {
+            writer,
+            spec,
+            length,
+            buffer: Vec::with_capacity(BUFFER_SIZE),
+            samples: 0,
+            trailer: Vec::new(),
+            finalized: false,
+        }
+    }
+
+    pub fn spec(&self) -> &WavSpec {
+        &self.spec
+    }
+
+    /// Frames written so far, an incomplete frame isn't counted.
+    pub fn frames(&self) -> u64 {
+        self.samples / self.spec.channels as u64
+    }
+
+    /// Write a sample between -1 and 1, samples of multiple channels are interleaved.
+    pub fn write_sample(&mut self, sample: f32) -> Result<(), WavError> {
+        self.spec.sample_format.encode_le(sample, &mut self.buffer);
+        self.samples += 1;
+        if self.buffer.len() >= BUFFER_SIZE {
+            self.writer.write_all(&self.buffer)?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    pub fn write_samples<T: IntoIterator<Item = f32>>(
+        &mut self,
+        samples: T,
+    ) -> Result<(), WavError> {
+        samples
+            .into_iter()
+            .try_for_each(|sample| self.write_sample(sample))
+    }
+
+    /// Write `frames` frames of silence, seekable writers skip over the signed
+    /// formats, whose silence is made of zeros, leaving a hole in sparse files.
+    pub fn write_silence(&mut self, frames: u64) -> Result<(), WavError> {
+        let samples = frames * self.spec.channels as u64;
+        let mut silence: Vec<u8> = Vec::new();
+        self.spec.sample_format.encode_le(0.0, &mut silence);
+        match self.length {
+            Length::Patched(seek) if silence.iter().all(|byte| *byte == 0) && samples > 0 => {
+                self.writer.write_all(&self.buffer)?;
+                self.buffer.clear();
+                // the last byte is written, so that the file reaches the end of the hole
+                let size = self.spec.data_size(frames) as i64;
+                seek(&mut self.writer, SeekFrom::Current(size - 1))?;
+                self.buffer.push(0);
+                self.samples += samples;
+                Ok(())
+            }
+            _ => self.write_samples((0..samples).map(|_| 0.0)),
+        }
+    }
+
+    /// Pad the data and write the final sizes in the header.
+    pub fn finalize(mut self) -> Result<(), WavError> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<(), WavError> {
+        if self.finalized {
+            return Ok(());
+        }
+        self.finalized = true;
+        let data_size = self.spec.data_size(self.frames());
+        // chunks are padded to an even size, but data of unknown length lasts until the
+        // end of the file and the padding would be read as a sample
+        if data_size % 2 == 1 && !matches!(self.length, Length::Unknown) {
+            self.buffer.push(0);
+        }
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.writer.write_all(&self.trailer)?;
+
+        match self.length {
+            Length::Patched(seek) => {
+                let end = seek(&mut self.writer, SeekFrom::Current(0))?;
+                seek(&mut self.writer, SeekFrom::Start(0))?;
+                self.writer
+                    .write_all(&self.spec.patchable_header(self.frames()))?;
+                seek(&mut self.writer, SeekFrom::Start(end))?;
+            }
+            Length::Declared(frames) if frames != self.frames() => {
+                return Err(WavError::LengthMismatch {
+                    declared: frames,
+                    written: self.frames(),
+                })
+            }
+            Length::Declared(_) | Length::Unknown => {}
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
 }
 
-pub fn write_wav(data: Vec<i16>, sample_rate: u32, writer: &mut dyn Write) -> io::Result<()> {
-    let nsamples = data.len() * 2;
-    writer.write_all(b"RIFF")?;
-    let rsize = make_bytes::<u32>(20 + nsamples as u32); // added 20 for the rest of the header
-    writer.write_all(&rsize)?; // WAVE chunk size
-
-    // WAVE chunk
-    writer.write_all(b"WAVE")?;
-
-    // fmt chunk
-    writer.write_all(b"fmt ")?;
-    writer.write_all(&make_bytes::<u32>(16))?; // fmt chunk size
-    writer.write_all(&make_bytes::<u16>(1))?; // format code (PCM)
-    writer.write_all(&make_bytes::<u16>(1))?; // number of channels
-    writer.write_all(&make_bytes::<u32>(sample_rate))?; // sample rate
-    writer.write_all(&make_bytes::<u32>(sample_rate))?; // data rate
-    writer.write_all(&make_bytes::<u16>(2))?; // block size
-    writer.write_all(&make_bytes::<u16>(16))?; // bits per sample
-
-    // data chunk
-    writer.write_all(b"data")?;
-    writer.write_all(&make_bytes::<u32>(nsamples as u32))?; // data chunk size
-    for half_word in data {
-        writer.write_all(&make_bytes(half_word as u16))?;
-    }
-
-    writer.flush()
+impl<W: Write> Drop for WavWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
 }
 
 #[test]
 fn test_file() {
+    use super::SampleFormat;
     use crate::polyphonia::{notable_notes, Note, Amplitude, SAMPLE_RATE};
     use std::fs::OpenOptions;
     fn get_writer(arg: &str) -> Box<dyn Write> {
         match arg {
             "-" => Box::new(io::stdout().lock()),
-            x if x.is_empty() => Box::new(io::stdout().lock()),
+            "" => Box::new(io::stdout().lock()),
             file_name => Box::new(
                 OpenOptions::new()
                     .write(true)
@@ -60,18 +217,159 @@ fn test_file() {
         }
     }
     let mut data_content = Vec::new();
-    data_content.extend_from_slice(&notable_notes::A4.audio_wave(3.0, &Amplitude::Medium));
-    data_content.extend_from_slice(&notable_notes::A4.audio_wave(3.0, &Amplitude::Silent));
-    data_content.extend_from_slice(&notable_notes::A4.audio_wave(3.0, &Amplitude::Low));
+    let wave = |volume| notable_notes::A4.audio_wave(3.0, &volume, SAMPLE_RATE);
+    data_content.extend_from_slice(&wave(Amplitude::Medium));
+    data_content.extend_from_slice(&wave(Amplitude::Silent));
+    data_content.extend_from_slice(&wave(Amplitude::Low));
     data_content.extend_from_slice(&Note::combine(
         &[notable_notes::A4, notable_notes::C4_SH, notable_notes::E0],
         3.0,
         &Amplitude::Medium,
+        SAMPLE_RATE,
     ));
     write_wav(
-        data_content,
-        SAMPLE_RATE,
+        &data_content,
+        &WavSpec::mono(SAMPLE_RATE, SampleFormat::S16),
         &mut get_writer("file_static.wav"),
     )
     .unwrap();
 }
+
+#[test]
+fn test_header() {
+    use super::wav_reader::{u16_at, u32_at};
+    use super::wav_spec::FORMAT_EXTENSIBLE;
+    use super::SampleFormat;
+
+    for (format, sample_rate) in [
+        (SampleFormat::U8, 8000),
+        (SampleFormat::S16, 16000),
+        (SampleFormat::S24, 48000),
+        (SampleFormat::S32, 48000),
+        (SampleFormat::F32, 48000),
+    ] {
+        let mut bytes: Vec<u8> = Vec::new();
+        let spec = WavSpec::mono(sample_rate, format);
+        write_wav(&[0.0, 0.5, -0.5], &spec, &mut bytes).unwrap();
+        let bytes_per_sample = format.bytes_per_sample() as u32;
+        assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+        let format_code = match spec.is_extensible() {
+            true => u16_at(&bytes, 44),
+            false => u16_at(&bytes, 20),
+        };
+        assert_eq!(format_code, if format.is_float() { 3 } else { 1 });
+        assert_eq!(
+            spec.is_extensible(),
+            u16_at(&bytes, 20) == FORMAT_EXTENSIBLE
+        );
+        assert_eq!(u32_at(&bytes, 24), sample_rate);
+        assert_eq!(u32_at(&bytes, 28), sample_rate * bytes_per_sample);
+        assert_eq!(u16_at(&bytes, 32) as u32, bytes_per_sample);
+        assert_eq!(u16_at(&bytes, 34), format.bits_per_sample());
+
+        let data = bytes.windows(4).position(|id| id == b"data").unwrap();
+        assert_eq!(u32_at(&bytes, data + 4), 3 * bytes_per_sample);
+        assert_eq!(bytes.len() % 2, 0);
+    }
+}
+
+#[test]
+fn test_stream_writer() {
+    use super::wav_reader::u32_at;
+    use super::{read_wav, SampleFormat};
+    use std::io::Cursor;
+
+    let samples = (0..5001)
+        .map(|i| f32::sin(i as f32 * 0.01))
+        .collect::<Vec<f32>>();
+    for format in [SampleFormat::U8, SampleFormat::S16, SampleFormat::F32] {
+        let spec = WavSpec::mono(8000, format);
+        let mut expected: Vec<u8> = Vec::new();
+        write_wav(&samples, &spec, &mut expected).unwrap();
+
+        // patched when finalized or dropped, keeping room for RF64 in a JUNK chunk
+        let without_junk = |file: &[u8]| {
+            let mut bytes = [&file[..12], &file[48..]].concat();
+            let riff_size = u32_at(&bytes, 4) - 36;
+            bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+            bytes
+        };
+        let mut file = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut file, spec).unwrap();
+        for chunk in samples.chunks(1000) {
+            writer.write_samples(chunk.iter().copied()).unwrap();
+        }
+        writer.finalize().unwrap();
+        assert_eq!(&file.get_ref()[12..16], b"JUNK");
+        assert_eq!(without_junk(file.get_ref()), expected);
+
+        let mut file = Cursor::new(Vec::new());
+        WavWriter::new(&mut file, spec)
+            .unwrap()
+            .write_samples(samples.iter().copied())
+            .unwrap();
+        assert_eq!(without_junk(file.get_ref()), expected);
+
+        // the length is known before the samples
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer =
+            WavWriter::with_known_length(&mut output, spec, samples.len() as u64).unwrap();
+        writer.write_samples(samples.iter().copied()).unwrap();
+        writer.finalize().unwrap();
+        assert_eq!(output, expected);
+
+        // or left unknown
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer = WavWriter::with_unknown_length(&mut output, spec).unwrap();
+        writer.write_samples(samples.iter().copied()).unwrap();
+        writer.finalize().unwrap();
+        assert_eq!(&output[4..8], &[0xFF; 4]);
+        // without the padding, that would be read as one more sample
+        let end = expected.len() - spec.data_size(samples.len() as u64) as usize % 2;
+        assert_eq!(output.len(), end);
+        assert_eq!(&output[end - 4..], &expected[end - 4..end]);
+        let wav = read_wav(&mut output.as_slice()).unwrap();
+        assert_eq!(wav.frames(), samples.len());
+    }
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut writer =
+        WavWriter::with_known_length(&mut output, WavSpec::mono(8000, SampleFormat::S16), 10)
+            .unwrap();
+    writer.write_samples([0.0; 9]).unwrap();
+    assert!(matches!(
+        writer.finalize(),
+        Err(WavError::LengthMismatch {
+            declared: 10,
+            written: 9
+        })
+    ));
+}
+
+#[test]
+fn test_metadata_writer() {
+    use super::wav_reader::u32_at;
+    use super::{read_wav, Cue, SampleFormat};
+
+    let metadata = WavMetadata {
+        title: Some(String::from("hello")),
+        comment: Some(String::from("hello world")),
+        software: Some(String::from("morse_traducer")),
+        cues: vec![Cue {
+            frame: 2,
+            label: String::from("e"),
+        }],
+    };
+    // an odd number of 8 bits samples is padded before the metadata
+    let spec = WavSpec::mono(8000, SampleFormat::U8);
+    let mut output: Vec<u8> = Vec::new();
+    let mut writer = WavWriter::with_metadata(&mut output, spec, 5, &metadata).unwrap();
+    writer.write_samples([0.0, 1.0, -1.0, 0.5, 0.0]).unwrap();
+    writer.finalize().unwrap();
+
+    let riff_size = u32_at(&output, 4);
+    assert_eq!(riff_size as usize, output.len() - 8);
+    let wav = read_wav(&mut output.as_slice()).unwrap();
+    assert_eq!(wav.frames(), 5);
+    assert_eq!(wav.metadata, metadata);
+}